@@ -0,0 +1,150 @@
+use image::RgbImage;
+use rayon::prelude::*;
+
+use crate::pixel_selector::{PixelSelectCriteria, PixelSelector};
+
+/// Ways to suggest a threshold range from the statistics of an image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThresholdSuggestion {
+    /// Splits the histogram into two classes (Otsu's method) and selects the upper one
+    Otsu,
+    /// Selects the values between two percentiles (in the range [0, 100])
+    Percentile(f32, f32),
+    /// Selects the given percentage of pixels with the highest values
+    Top(f32),
+}
+
+/// Returns the highest value the criteria can have
+pub fn criteria_max(criteria: PixelSelectCriteria) -> u64 {
+    match criteria {
+        PixelSelectCriteria::Hue => 360,
+        PixelSelectCriteria::Brightness | PixelSelectCriteria::Saturation => 255,
+    }
+}
+
+/// Counts how many pixels of the image have each value of the criteria
+pub fn histogram(img: &RgbImage, criteria: PixelSelectCriteria) -> Vec<u64> {
+    let value_function = PixelSelector::get_value_function(criteria);
+    let size = criteria_max(criteria) as usize + 1;
+    img.pixels()
+        .collect::<Vec<_>>()
+        .par_iter()
+        .fold(
+            || vec![0u64; size],
+            |mut hist, p| {
+                hist[(value_function(p) as usize).min(size - 1)] += 1;
+                hist
+            },
+        )
+        .reduce(
+            || vec![0u64; size],
+            |a, b| a.iter().zip(b).map(|(a, b)| a + b).collect(),
+        )
+}
+
+/// Returns the value that splits the histogram into two classes with the highest variance between them
+pub fn otsu_threshold(hist: &[u64]) -> u64 {
+    let total: u64 = hist.iter().sum();
+    let sum_all: f64 = hist.iter().enumerate().map(|(v, &n)| v as f64 * n as f64).sum();
+
+    let (mut best_threshold, mut best_variance) = (0, 0.0);
+    let (mut count_below, mut sum_below) = (0u64, 0.0);
+    for (v, &n) in hist.iter().enumerate() {
+        count_below += n;
+        sum_below += v as f64 * n as f64;
+        let count_above = total - count_below;
+        if count_below == 0 || count_above == 0 {
+            continue;
+        }
+        let mean_below = sum_below / count_below as f64;
+        let mean_above = (sum_all - sum_below) / count_above as f64;
+        let variance = count_below as f64 * count_above as f64 * (mean_below - mean_above).powi(2);
+        if variance > best_variance {
+            best_variance = variance;
+            best_threshold = v as u64;
+        }
+    }
+    best_threshold
+}
+
+/// Returns the smallest value that at least `percent` percent of the pixels are below or equal to
+pub fn percentile(hist: &[u64], percent: f32) -> u64 {
+    let total: u64 = hist.iter().sum();
+    let target = (total as f64 * percent.clamp(0.0, 100.0) as f64 / 100.0).ceil() as u64;
+    let mut count = 0;
+    for (v, &n) in hist.iter().enumerate() {
+        count += n;
+        if count >= target.max(1) {
+            return v as u64;
+        }
+    }
+    hist.len().saturating_sub(1) as u64
+}
+
+/// Suggests a (min, max) threshold range for the criteria, based on the histogram of the image
+pub fn suggest_threshold(img: &RgbImage, criteria: PixelSelectCriteria, method: ThresholdSuggestion) -> (u64, u64) {
+    let hist = histogram(img, criteria);
+    let max = criteria_max(criteria);
+    match method {
+        ThresholdSuggestion::Otsu => ((otsu_threshold(&hist) + 1).min(max), max),
+        ThresholdSuggestion::Percentile(low, high) => (percentile(&hist, low), percentile(&hist, high)),
+        ThresholdSuggestion::Top(percent) => (percentile(&hist, 100.0 - percent), max),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    /// Two bumps around 50 and 200
+    fn bimodal() -> Vec<u64> {
+        let mut hist = vec![0; 256];
+        for (center, count) in [(50, 30), (200, 20)] {
+            for d in 0..5 {
+                hist[center - 2 + d] = count - 5 * (d as i64 - 2).unsigned_abs();
+            }
+        }
+        hist
+    }
+
+    #[test]
+    fn otsu_splits_between_the_modes() {
+        let threshold = otsu_threshold(&bimodal());
+        assert!((52..198).contains(&threshold), "threshold {} is inside of a mode", threshold);
+    }
+
+    #[test]
+    fn percentiles_reach_the_ends() {
+        let hist = bimodal();
+        assert_eq!(percentile(&hist, 0.0), 48);
+        assert_eq!(percentile(&hist, 100.0), 202);
+        // Out of range percentages are clamped
+        assert_eq!(percentile(&hist, -10.0), 48);
+        assert_eq!(percentile(&hist, 250.0), 202);
+        // The first mode has 120 of 190 pixels (20, 25, 30, 25, 20), half of them are reached at 51
+        assert_eq!(percentile(&hist, 50.0), 51);
+        assert_eq!(percentile(&hist, 70.0), 199);
+    }
+
+    #[test]
+    fn suggestions_for_empty_and_single_color_images() {
+        let methods = [ThresholdSuggestion::Otsu, ThresholdSuggestion::Percentile(10.0, 90.0), ThresholdSuggestion::Top(20.0)];
+        let criterias = [PixelSelectCriteria::Hue, PixelSelectCriteria::Brightness, PixelSelectCriteria::Saturation];
+        for criteria in criterias {
+            for method in methods {
+                let (min, max) = suggest_threshold(&RgbImage::new(0, 0), criteria, method);
+                assert!(min <= max && max <= criteria_max(criteria));
+            }
+        }
+
+        let gray = RgbImage::from_pixel(8, 8, Rgb([120, 120, 120]));
+        let brightness = PixelSelector::get_value_function(PixelSelectCriteria::Brightness)(&Rgb([120, 120, 120])) as u64;
+        let suggest = |method| suggest_threshold(&gray, PixelSelectCriteria::Brightness, method);
+        assert_eq!(suggest(ThresholdSuggestion::Percentile(0.0, 100.0)), (brightness, brightness));
+        assert_eq!(suggest(ThresholdSuggestion::Top(50.0)), (brightness, 255));
+        // There is nothing to split, but the range still contains the only color
+        let (min, max) = suggest(ThresholdSuggestion::Otsu);
+        assert!(min <= brightness && brightness <= max);
+    }
+}
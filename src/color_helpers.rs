@@ -2,8 +2,8 @@
 use image::{Pixel, Rgb};
 use std::cmp::{max, min};
 
-/// Returns the hue value of a pixel in the range [0, 360]
-pub fn get_hue(&pixel: &Rgb<u8>) -> u16 {
+/// Returns the hue value of a pixel in the range [0, 360), without rounding
+pub fn get_hue_f32(&pixel: &Rgb<u8>) -> f32 {
     let channels = pixel.channels();
     let r: f32 = channels[0] as f32 / 255.0;
     let g: f32 = channels[1] as f32 / 255.0;
@@ -13,7 +13,7 @@ pub fn get_hue(&pixel: &Rgb<u8>) -> u16 {
     if (r >= g && g >= b) {
         //orange
         if r == b {
-            return 0;
+            return 0.0;
         }
         hue = 60.0 * (g - b) / (r - b);
     } else if (g > r && r >= b) {
@@ -33,14 +33,24 @@ pub fn get_hue(&pixel: &Rgb<u8>) -> u16 {
         hue = 360.0 - 60.0 * (b - g) / (r - g);
     }
 
-    return hue as u16;
+    return hue;
 }
 
-/// returns the brightness of a pixel in the range [0, 255]
-pub fn get_brightness(&p: &Rgb<u8>) -> u16 {
+/// Returns the hue value of a pixel in the range [0, 360]
+pub fn get_hue(pixel: &Rgb<u8>) -> u16 {
+    get_hue_f32(pixel) as u16
+}
+
+/// returns the brightness of a pixel in the range [0, 255], without rounding
+pub fn get_brightness_f32(&p: &Rgb<u8>) -> f32 {
     let channels = p.channels();
     let (r, g, b) = (channels[0] as f32, channels[1] as f32, channels[2] as f32);
-    (0.2126 * r + 0.7152 * g + 0.0722 * b) as u16
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+/// returns the brightness of a pixel in the range [0, 255]
+pub fn get_brightness(p: &Rgb<u8>) -> u16 {
+    get_brightness_f32(p) as u16
 }
 
 /// tries to calculate the brightness of a pixel but with added casting and overflowing issues added into the mix.
@@ -61,3 +71,34 @@ pub fn get_saturation(&p: &Rgb<u8>) -> u16 {
     let minrgb: u16 = min(channels[0], min(channels[1], channels[2])).into();
     (255 * (maxrgb - minrgb) / maxrgb) as u16
 }
+
+/// returns the saturation of a pixel in the range [0, 255], without rounding
+pub fn get_saturation_f32(&p: &Rgb<u8>) -> f32 {
+    let channels = &p.channels();
+    let maxrgb = max(channels[0], max(channels[1], channels[2])) as f32;
+    if maxrgb == 0.0 {
+        return 0.0;
+    }
+    let minrgb = min(channels[0], min(channels[1], channels[2])) as f32;
+    255.0 * (maxrgb - minrgb) / maxrgb
+}
+
+/// returns the CIELAB color of a pixel (L in the range [0, 100]), so distances between colors match how different they look
+pub fn get_lab(&p: &Rgb<u8>) -> (f32, f32, f32) {
+    // sRGB to linear RGB
+    let linear = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    };
+    let channels = p.channels();
+    let (r, g, b) = (linear(channels[0]), linear(channels[1]), linear(channels[2]));
+
+    // linear RGB to XYZ, relative to the D65 white point
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+    let f = |t: f32| if t > 0.008856 { t.cbrt() } else { 7.787 * t + 16.0 / 116.0 };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    (116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+}
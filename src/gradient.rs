@@ -0,0 +1,85 @@
+use image::Rgb;
+use rayon::prelude::*;
+use std::ops::Deref;
+
+use crate::color_helpers::get_brightness;
+
+/// Returns the brightness of every pixel in the range [0, 255]
+pub fn brightness_map<P: Deref<Target = Rgb<u8>> + Sync>(pixels: &[P]) -> Vec<f32> {
+    pixels.par_iter().map(|p| get_brightness(p) as f32).collect()
+}
+
+/// Applies the sobel operator to a map of values (f.e. brightness) and returns the gradient (gx, gy) of every pixel.
+/// Pixels outside of the image are treated like the nearest pixel on the border
+pub fn sobel(values: &[f32], w: u64, h: u64) -> Vec<(f32, f32)> {
+    let (w, h) = (w as i64, h as i64);
+    let at = |x: i64, y: i64| values[(y.clamp(0, h - 1) * w + x.clamp(0, w - 1)) as usize];
+
+    (0..w * h)
+        .into_par_iter()
+        .map(|i| {
+            let (x, y) = (i % w, i / w);
+            let gx = (at(x + 1, y - 1) + 2.0 * at(x + 1, y) + at(x + 1, y + 1))
+                - (at(x - 1, y - 1) + 2.0 * at(x - 1, y) + at(x - 1, y + 1));
+            let gy = (at(x - 1, y + 1) + 2.0 * at(x, y + 1) + at(x + 1, y + 1))
+                - (at(x - 1, y - 1) + 2.0 * at(x, y - 1) + at(x + 1, y - 1));
+            (gx, gy)
+        })
+        .collect()
+}
+
+/// Returns the edge strength of every pixel in the range [0, 255]
+pub fn edge_strength(pixels: &[&mut Rgb<u8>], w: u64, h: u64) -> Vec<u16> {
+    sobel(&brightness_map(pixels), w, h)
+        .into_par_iter()
+        // The magnitude can get up to ~1442, a quarter of that is a good enough range
+        .map(|(gx, gy)| ((gx * gx + gy * gy).sqrt() / 4.0).min(255.0) as u16)
+        .collect()
+}
+
+/// Blurs a map of values with a box of the given radius (separately in x and y direction)
+pub fn box_blur(values: &[f32], w: u64, h: u64, radius: u64) -> Vec<f32> {
+    if radius == 0 {
+        return values.to_vec();
+    }
+    let (w, h, r) = (w as i64, h as i64, radius as i64);
+    let blur_line = |len: i64, at: &dyn Fn(i64) -> f32| -> Vec<f32> {
+        // Running sum over the window, borders are clamped like in sobel
+        let mut sum: f32 = (-r..=r).map(|i| at(i.clamp(0, len - 1))).sum();
+        let mut line = Vec::with_capacity(len as usize);
+        for i in 0..len {
+            line.push(sum / (2 * r + 1) as f32);
+            sum += at((i + r + 1).min(len - 1)) - at((i - r).max(0));
+        }
+        line
+    };
+
+    let rows: Vec<f32> = (0..h)
+        .into_par_iter()
+        .flat_map_iter(|y| blur_line(w, &|x| values[(y * w + x) as usize]))
+        .collect();
+    let columns: Vec<Vec<f32>> = (0..w)
+        .into_par_iter()
+        .map(|x| blur_line(h, &|y| rows[(y * w + x) as usize]))
+        .collect();
+    (0..w * h).map(|i| columns[(i % w) as usize][(i / w) as usize]).collect()
+}
+
+/// Returns the direction along the edges of every pixel as a unit vector.
+/// The structure tensor is smoothed with the given radius first, so the directions follow larger structures.
+/// The sign of a direction is arbitrary
+pub fn edge_directions<P: Deref<Target = Rgb<u8>> + Sync>(pixels: &[P], w: u64, h: u64, smoothing: u64) -> Vec<(f32, f32)> {
+    let gradients = sobel(&brightness_map(pixels), w, h);
+    let jxx = box_blur(&gradients.par_iter().map(|(gx, _)| gx * gx).collect::<Vec<_>>(), w, h, smoothing);
+    let jxy = box_blur(&gradients.par_iter().map(|(gx, gy)| gx * gy).collect::<Vec<_>>(), w, h, smoothing);
+    let jyy = box_blur(&gradients.par_iter().map(|(_, gy)| gy * gy).collect::<Vec<_>>(), w, h, smoothing);
+
+    (0..(w * h) as usize)
+        .into_par_iter()
+        .map(|i| {
+            // Orientation of the gradient, turned by 90° to go along the edge
+            let angle = 0.5 * (2.0 * jxy[i]).atan2(jxx[i] - jyy[i]) + std::f32::consts::FRAC_PI_2;
+            (angle.cos(), angle.sin())
+        })
+        .collect()
+}
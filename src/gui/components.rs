@@ -9,9 +9,10 @@ use egui::{Align2, Frame, Label, SliderClamping, Stroke, TextBuffer};
 use egui_flex::FlexInstance;
 use log::info;
 use pixelsortery::{
-    path_creator::PathCreator,
-    pixel_selector::{PixelSelectCriteria, PixelSelector},
-    span_sorter::{SortingAlgorithm, SortingCriteria},
+    analysis::{self, ThresholdSuggestion},
+    path_creator::{CurveFunction, FlowSource, Formula, PathCreator, RegionOrder, TileDirection, TileDirections, TileOrder},
+    pixel_selector::{NoiseKind, PixelSelectCriteria, PixelSelector, RandomDistribution},
+    span_sorter::{DebugColoring, KeySource, SortingAlgorithm, SortingCriteria, TiePolicy},
 };
 
 use super::*;
@@ -22,6 +23,112 @@ fn important_text(s: &str) -> RichText {
     RichText::new(s).size(14.0)
 }
 
+/// Buttons to choose how the pixels inside of regions are ordered
+fn region_order_buttons(ui: &mut Ui, order: &mut RegionOrder) {
+    ui.horizontal(|ui| {
+        ui.selectable_value(order, RegionOrder::Rows, "Rows");
+        ui.selectable_value(order, RegionOrder::RandomAngle, "Random Angle");
+        ui.selectable_value(order, RegionOrder::Spiral, "Spiral");
+    });
+}
+
+/// Grid rows for the center and (unless it's only about the center) the ellipse of round paths
+fn center_and_ellipse_rows(ui: &mut Ui, options: &mut RoundOptions, ellipse: bool) {
+    ui.label(important_text("Center"));
+    ui.horizontal(|ui| {
+        ui.add(egui::DragValue::new(&mut options.center.0).range(0.0..=1.0).speed(0.005).prefix("x: "));
+        ui.add(egui::DragValue::new(&mut options.center.1).range(0.0..=1.0).speed(0.005).prefix("y: "));
+    });
+    ui.end_row();
+    ui.label("");
+    ui.label(RichText::new("Click on the image to place the center").italics());
+    ui.end_row();
+    if ellipse {
+        ui.label(important_text("Ellipse"));
+        let slider = egui::Slider::new(&mut options.ellipse, 0.1..=10.0)
+            .logarithmic(true)
+            .clamping(SliderClamping::Never)
+            .max_decimals(2);
+        ui.add(slider);
+        ui.end_row();
+    }
+}
+
+/// Draws a min and a max slider, colored and capped according to the criteria, with the criteria bar between them
+fn criteria_range_sliders(
+    ui: &mut Ui,
+    min: &mut u64,
+    max: &mut u64,
+    criteria: PixelSelectCriteria,
+    min_label: &str,
+    max_label: &str,
+) {
+    let (cap, selector_suffix) = if criteria == PixelSelectCriteria::Hue {
+        (360, "°")
+    } else {
+        (256, "")
+    };
+
+    // Get slider colors and image
+    // HSVA::new(hue, saturation, brightness, alpha)
+    let (mincol, maxcol, criteria_image) = match criteria {
+        PixelSelectCriteria::Hue => (
+            Hsva::new(*min as f32 / 360.0, 1.0, 1.0, 1.0).into(),
+            Hsva::new(*max as f32 / 360.0, 1.0, 1.0, 1.0).into(),
+            Image::new(egui::include_image!("../../assets/hue-bar.png")),
+        ),
+        PixelSelectCriteria::Brightness => (
+            Hsva::new(1.0, 0.0, *min as f32 / 256.0, 1.0).into(),
+            Hsva::new(1.0, 0.0, *max as f32 / 256.0, 1.0).into(),
+            Image::new(egui::include_image!("../../assets/brightness-bar.png")),
+        ),
+        PixelSelectCriteria::Saturation => (
+            Hsva::new(1.0, *min as f32 / 256.0, 1.0, 1.0).into(),
+            Hsva::new(1.0, *max as f32 / 256.0, 1.0, 1.0).into(),
+            Image::new(egui::include_image!("../../assets/saturation-bar.png")),
+        ),
+    };
+
+    ui.label(important_text(min_label));
+    ui.scope(|ui| {
+        ui.style_mut().visuals.selection.bg_fill = mincol;
+        let min_slider = egui::Slider::new(min, 0..=cap)
+            .handle_shape(HandleShape::Rect { aspect_ratio: 0.5 })
+            .trailing_fill(true)
+            .suffix(selector_suffix)
+            .drag_value_speed(0.2)
+            .smart_aim(false);
+        if ui.add(min_slider).dragged() {
+            *max = (*max).clamp(*min, u64::MAX);
+        };
+    });
+    ui.end_row();
+
+    ui.label("");
+    ui.add(
+        criteria_image
+            .maintain_aspect_ratio(false)
+            .fit_to_exact_size([ui.style().spacing.slider_width, 15.0].into()),
+    );
+    ui.end_row();
+
+    ui.label(important_text(max_label));
+    ui.scope(|ui| {
+        ui.style_mut().visuals.selection.bg_fill = maxcol;
+        let max_slider = egui::Slider::new(max, 0..=cap)
+            .handle_shape(HandleShape::Rect { aspect_ratio: 0.5 })
+            .trailing_fill(true)
+            .suffix(selector_suffix)
+            .drag_value_speed(0.2)
+            .smart_aim(false);
+
+        if ui.add(max_slider).dragged() {
+            *min = (*min).clamp(u64::MIN, *max);
+        };
+    });
+    ui.end_row();
+}
+
 impl PixelsorterGui {
     pub(super) fn path_combo_box(&mut self, ui: &mut Ui, id: u64) {
         let available_paths = vec![
@@ -29,14 +136,29 @@ impl PixelsorterGui {
             PathCreator::AllVertically,
             PathCreator::HorizontalLines,
             PathCreator::VerticalLines,
-            PathCreator::Rays,
-            PathCreator::Circles,
-            PathCreator::Spiral,
+            PathCreator::Rays(self.values.path_round),
+            PathCreator::Circles(self.values.path_round),
+            PathCreator::Spiral(self.values.path_round),
             PathCreator::SquareSpiral,
             PathCreator::RectSpiral,
             PathCreator::Diagonally(self.values.path_diagonally_val),
-            PathCreator::Hilbert,
-            self.values.path_x3.clone(),
+            PathCreator::Rotated(self.values.path_rotated_val),
+            self.values.path_hilbert,
+            PathCreator::Moore,
+            PathCreator::Peano,
+            PathCreator::ZOrder,
+            PathCreator::Serpentine,
+            self.values.path_curve,
+            self.values.path_waves,
+            self.values.path_polygon,
+            self.values.path_star,
+            self.values.path_flow_image,
+            self.values.path_flow_noise,
+            self.values.path_voronoi,
+            self.values.path_segments,
+            self.values.path_strokes,
+            self.values.path_tiles,
+            self.values.path_walk,
         ];
         let selected_text = self.values.path.to_string();
 
@@ -50,9 +172,11 @@ impl PixelsorterGui {
                     }
                 });
 
-            // Reverse checkbox
+            // Reverse and polar checkboxes
             ui.with_layout(egui::Layout::right_to_left(Align::Center), |ui| {
                 ui.checkbox(&mut self.values.reverse, "Reverse?");
+                ui.checkbox(&mut self.values.polar, "Polar")
+                    .on_hover_text("Sort in polar coordinates, so lines become circles or rays. Click the image to place the center");
             });
         });
         ui.end_row();
@@ -82,21 +206,475 @@ impl PixelsorterGui {
                     });
                 ui.end_row();
             },
-            PathCreator::X3(ref mut offset_y, ref mut scalar_w, ref mut scalar_l) => {
+            PathCreator::Rotated(ref mut angle) => {
+                ui.label("");
+                egui::Grid::new(format!("path_options_grid_{}", id))
+                    .num_columns(2)
+                    .min_row_height(25.0)
+                    .show(ui, |ui| {
+                        ui.label(important_text("Angle"));
+                        let slider = egui::Slider::new(angle, 0.0..=360.0)
+                            .suffix("°")
+                            .clamping(SliderClamping::Edits)
+                            .drag_value_speed(0.2)
+                            .max_decimals(1)
+                            .smart_aim(false);
+                        ui.add(slider);
+                        ui.end_row();
+                        // Save for when we reselect rotated
+                        self.values.path_rotated_val = *angle;
+                    });
+                ui.end_row();
+            },
+            PathCreator::Rays(ref mut options)
+            | PathCreator::Circles(ref mut options)
+            | PathCreator::Spiral(ref mut options) => {
+                let is_rays = matches!(current_path, PathCreator::Rays(_));
+                ui.label("");
+                egui::Grid::new(format!("path_options_grid_{}", id))
+                    .num_columns(2)
+                    .min_row_height(25.0)
+                    .show(ui, |ui| {
+                        // Rays are straight lines, they only need a center
+                        center_and_ellipse_rows(ui, options, !is_rays);
+                        if !is_rays {
+                            ui.label(important_text("Start Angle"));
+                            let slider = egui::Slider::new(&mut options.start_angle, -180.0..=180.0)
+                                .suffix("°")
+                                .clamping(SliderClamping::Edits)
+                                .drag_value_speed(0.2)
+                                .max_decimals(1)
+                                .smart_aim(false);
+                            ui.add(slider);
+                            ui.end_row();
+                        }
+                        // Save for when we reselect a round path
+                        self.values.path_round = options.clone();
+                    });
+                ui.end_row();
+            }
+            PathCreator::Hilbert { ref mut block_size, ref mut alternate } => {
+                ui.label("");
+                egui::Grid::new(format!("path_options_grid_{}", id))
+                    .num_columns(2)
+                    .min_row_height(25.0)
+                    .show(ui, |ui| {
+                        let mut tiled = block_size.is_some();
+                        ui.label(important_text("Tiles"));
+                        if ui.checkbox(&mut tiled, "").changed() {
+                            *block_size = tiled.then_some(64);
+                        }
+                        ui.end_row();
+                        if let Some(size) = block_size {
+                            ui.label(important_text("Block Size"));
+                            let slider = egui::Slider::new(size, 2..=1024)
+                                .logarithmic(true)
+                                .suffix("px")
+                                .clamping(SliderClamping::Never)
+                                .drag_value_speed(0.2)
+                                .smart_aim(false);
+                            ui.add(slider);
+                            ui.end_row();
+                            ui.label(important_text("Alternate"));
+                            ui.checkbox(alternate, "");
+                            ui.end_row();
+                        }
+                        // Save for when we reselect hilbert
+                        self.values.path_hilbert = PathCreator::Hilbert { block_size: *block_size, alternate: *alternate };
+                    });
+                ui.end_row();
+            }
+            PathCreator::Waves { ref mut amplitude, ref mut wavelength, ref mut phase, ref mut angle } => {
+                ui.label("");
+                egui::Grid::new(format!("path_options_grid_{}", id))
+                    .num_columns(2)
+                    .min_row_height(25.0)
+                    .show(ui, |ui| {
+                        ui.label(important_text("Amplitude"));
+                        let slider = egui::Slider::new(amplitude, 0.0..=200.0)
+                            .suffix("px")
+                            .clamping(SliderClamping::Never)
+                            .drag_value_speed(0.2)
+                            .max_decimals(1);
+                        ui.add(slider);
+                        ui.end_row();
+                        ui.label(important_text("Wavelength"));
+                        let slider = egui::Slider::new(wavelength, 2.0..=2000.0)
+                            .suffix("px")
+                            .logarithmic(true)
+                            .clamping(SliderClamping::Never)
+                            .max_decimals(1);
+                        ui.add(slider);
+                        ui.end_row();
+                        ui.label(important_text("Phase"));
+                        let slider = egui::Slider::new(phase, 0.0..=360.0)
+                            .suffix("°")
+                            .clamping(SliderClamping::Edits)
+                            .drag_value_speed(0.2)
+                            .max_decimals(1)
+                            .smart_aim(false);
+                        ui.add(slider);
+                        ui.end_row();
+                        ui.label(important_text("Angle"));
+                        let slider = egui::Slider::new(angle, 0.0..=360.0)
+                            .suffix("°")
+                            .clamping(SliderClamping::Edits)
+                            .drag_value_speed(0.2)
+                            .max_decimals(1)
+                            .smart_aim(false);
+                        ui.add(slider);
+                        ui.end_row();
+                        // Save for when we reselect waves
+                        self.values.path_waves = PathCreator::Waves {
+                            amplitude: *amplitude,
+                            wavelength: *wavelength,
+                            phase: *phase,
+                            angle: *angle,
+                        };
+                    });
+                ui.end_row();
+            }
+            PathCreator::Polygon { ref mut sides, ref mut rotation, ref mut options } => {
+                ui.label("");
+                egui::Grid::new(format!("path_options_grid_{}", id))
+                    .num_columns(2)
+                    .min_row_height(25.0)
+                    .show(ui, |ui| {
+                        ui.label(important_text("Sides"));
+                        ui.add(egui::Slider::new(sides, 3..=16).clamping(SliderClamping::Never));
+                        ui.end_row();
+                        center_and_ellipse_rows(ui, options, true);
+                        ui.label(important_text("Rotation"));
+                        let slider = egui::Slider::new(rotation, 0.0..=360.0)
+                            .suffix("°")
+                            .clamping(SliderClamping::Edits)
+                            .drag_value_speed(0.2)
+                            .max_decimals(1)
+                            .smart_aim(false);
+                        ui.add(slider);
+                        ui.end_row();
+                        // Save for when we reselect polygons
+                        self.values.path_polygon = PathCreator::Polygon { sides: *sides, rotation: *rotation, options: *options };
+                    });
+                ui.end_row();
+            }
+            PathCreator::Star { ref mut points, ref mut inner, ref mut rotation, ref mut options } => {
+                ui.label("");
+                egui::Grid::new(format!("path_options_grid_{}", id))
+                    .num_columns(2)
+                    .min_row_height(25.0)
+                    .show(ui, |ui| {
+                        ui.label(important_text("Points"));
+                        ui.add(egui::Slider::new(points, 3..=16).clamping(SliderClamping::Never));
+                        ui.end_row();
+                        center_and_ellipse_rows(ui, options, true);
+                        ui.label(important_text("Inner Radius"));
+                        let slider = egui::Slider::new(inner, 0.05..=1.0)
+                            .clamping(SliderClamping::Always)
+                            .max_decimals(2);
+                        ui.add(slider);
+                        ui.end_row();
+                        ui.label(important_text("Rotation"));
+                        let slider = egui::Slider::new(rotation, 0.0..=360.0)
+                            .suffix("°")
+                            .clamping(SliderClamping::Edits)
+                            .drag_value_speed(0.2)
+                            .max_decimals(1)
+                            .smart_aim(false);
+                        ui.add(slider);
+                        ui.end_row();
+                        // Save for when we reselect stars
+                        self.values.path_star =
+                            PathCreator::Star { points: *points, inner: *inner, rotation: *rotation, options: *options };
+                    });
+                ui.end_row();
+            }
+            PathCreator::Flow(ref mut source) => {
+                ui.label("");
+                egui::Grid::new(format!("path_options_grid_{}", id))
+                    .num_columns(2)
+                    .min_row_height(25.0)
+                    .show(ui, |ui| {
+                        match source {
+                            FlowSource::Image { smoothing } => {
+                                ui.label(important_text("Smoothing"));
+                                let slider = egui::Slider::new(smoothing, 0..=64)
+                                    .suffix("px")
+                                    .clamping(SliderClamping::Never)
+                                    .drag_value_speed(0.2);
+                                ui.add(slider);
+                                ui.end_row();
+                                // Save for when we reselect the image flow
+                                self.values.path_flow_image = PathCreator::Flow(*source);
+                            }
+                            FlowSource::Noise { scale, seed } => {
+                                ui.label(important_text("Scale"));
+                                let slider = egui::Slider::new(scale, 1..=1000)
+                                    .logarithmic(true)
+                                    .clamping(SliderClamping::Never)
+                                    .suffix("px")
+                                    .drag_value_speed(0.2)
+                                    .smart_aim(false);
+                                ui.add(slider);
+                                ui.end_row();
+                                ui.label(important_text("Seed"));
+                                ui.horizontal(|ui| {
+                                    ui.add(egui::DragValue::new(seed));
+                                    if ui.button("Randomize").clicked() {
+                                        *seed = rand::random();
+                                    }
+                                });
+                                ui.end_row();
+                                // Save for when we reselect the noise flow
+                                self.values.path_flow_noise = PathCreator::Flow(*source);
+                            }
+                        }
+                    });
+                ui.end_row();
+            }
+            PathCreator::Voronoi { ref mut size, ref mut jitter, ref mut order, ref mut seed } => {
+                ui.label("");
+                egui::Grid::new(format!("path_options_grid_{}", id))
+                    .num_columns(2)
+                    .min_row_height(25.0)
+                    .show(ui, |ui| {
+                        ui.label(important_text("Cell Size"));
+                        let slider = egui::Slider::new(size, 4..=1000)
+                            .logarithmic(true)
+                            .suffix("px")
+                            .clamping(SliderClamping::Never)
+                            .drag_value_speed(0.2)
+                            .smart_aim(false);
+                        ui.add(slider);
+                        ui.end_row();
+                        ui.label(important_text("Jitter"));
+                        ui.add(egui::Slider::new(jitter, 0.0..=1.0).max_decimals(2));
+                        ui.end_row();
+                        ui.label(important_text("Order"));
+                        region_order_buttons(ui, order);
+                        ui.end_row();
+                        ui.label(important_text("Seed"));
+                        ui.horizontal(|ui| {
+                            ui.add(egui::DragValue::new(seed));
+                            if ui.button("Randomize").clicked() {
+                                *seed = rand::random();
+                            }
+                        });
+                        ui.end_row();
+                        // Save for when we reselect voronoi
+                        self.values.path_voronoi = PathCreator::Voronoi { size: *size, jitter: *jitter, order: *order, seed: *seed };
+                    });
+                ui.end_row();
+            }
+            PathCreator::Tiles { ref mut size, ref mut directions, ref mut order } => {
+                ui.label("");
+                egui::Grid::new(format!("path_options_grid_{}", id))
+                    .num_columns(2)
+                    .min_row_height(25.0)
+                    .show(ui, |ui| {
+                        ui.label(important_text("Tile Size"));
+                        let slider = egui::Slider::new(size, 4..=1000)
+                            .logarithmic(true)
+                            .suffix("px")
+                            .clamping(SliderClamping::Never)
+                            .drag_value_speed(0.2)
+                            .smart_aim(false);
+                        ui.add(slider);
+                        ui.end_row();
+                        let arrow = |d: TileDirection| match d {
+                            TileDirection::Right => "→",
+                            TileDirection::Left => "←",
+                            TileDirection::Down => "↓",
+                            TileDirection::Up => "↑",
+                        };
+                        ui.label(important_text("Directions"));
+                        ui.label(RichText::new(directions.as_slice().iter().map(|&d| arrow(d)).collect::<String>()).heading());
+                        ui.end_row();
+                        ui.label("");
+                        ui.horizontal(|ui| {
+                            let mut list = directions.as_slice().to_vec();
+                            for d in [TileDirection::Right, TileDirection::Left, TileDirection::Down, TileDirection::Up] {
+                                if ui.button(format!("+{}", arrow(d))).clicked() {
+                                    list.push(d);
+                                }
+                            }
+                            if ui.add_enabled(list.len() > 1, Button::new("⌫")).clicked() {
+                                list.pop();
+                            }
+                            // Ignores adding more than the maximum
+                            if let Some(new_directions) = TileDirections::new(&list) {
+                                *directions = new_directions;
+                            }
+                        });
+                        ui.end_row();
+                        ui.label(important_text("Order"));
+                        ui.horizontal(|ui| {
+                            if ui.selectable_label(*order == TileOrder::Cycle, "Cycle").clicked() {
+                                *order = TileOrder::Cycle;
+                            }
+                            if ui.selectable_label(*order != TileOrder::Cycle, "Random").clicked() && *order == TileOrder::Cycle {
+                                *order = TileOrder::Random { seed: 0 };
+                            }
+                        });
+                        ui.end_row();
+                        if let TileOrder::Random { seed } = order {
+                            ui.label(important_text("Seed"));
+                            ui.horizontal(|ui| {
+                                ui.add(egui::DragValue::new(seed));
+                                if ui.button("Randomize").clicked() {
+                                    *seed = rand::random();
+                                }
+                            });
+                            ui.end_row();
+                        }
+                        // Save for when we reselect tiles
+                        self.values.path_tiles = PathCreator::Tiles { size: *size, directions: *directions, order: *order };
+                    });
+                ui.end_row();
+            }
+            PathCreator::RandomWalk { ref mut angle, ref mut bias, ref mut seed } => {
                 ui.label("");
                 egui::Grid::new(format!("path_options_grid_{}", id))
                     .num_columns(2)
                     .min_row_height(25.0)
                     .show(ui, |ui| {
-                        let slider_a = egui::Slider::new(offset_y, -h2..=h2)
+                        ui.label(important_text("Angle"));
+                        let slider = egui::Slider::new(angle, 0.0..=360.0)
+                            .suffix("°")
+                            .clamping(SliderClamping::Edits)
+                            .drag_value_speed(0.2)
+                            .max_decimals(1)
+                            .smart_aim(false);
+                        ui.add(slider);
+                        ui.end_row();
+                        ui.label(important_text("Bias"));
+                        ui.add(egui::Slider::new(bias, 0.0..=10.0).max_decimals(2));
+                        ui.end_row();
+                        ui.label(important_text("Seed"));
+                        ui.horizontal(|ui| {
+                            ui.add(egui::DragValue::new(seed));
+                            if ui.button("Randomize").clicked() {
+                                *seed = rand::random();
+                            }
+                        });
+                        ui.end_row();
+                        // Save for when we reselect the random walk
+                        self.values.path_walk = PathCreator::RandomWalk { angle: *angle, bias: *bias, seed: *seed };
+                    });
+                ui.end_row();
+            }
+            PathCreator::Strokes { ref mut width } => {
+                ui.label("");
+                egui::Grid::new(format!("path_options_grid_{}", id))
+                    .num_columns(2)
+                    .min_row_height(25.0)
+                    .show(ui, |ui| {
+                        ui.label(important_text("Brush Width"));
+                        let slider = egui::Slider::new(width, 1..=300)
+                            .logarithmic(true)
+                            .suffix("px")
+                            .clamping(SliderClamping::Never)
+                            .drag_value_speed(0.2)
+                            .smart_aim(false);
+                        ui.add(slider);
+                        ui.end_row();
+                        ui.label(important_text(&format!("{} Strokes", self.values.strokes.len())));
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(!self.values.strokes.is_empty(), Button::new("Undo")).clicked() {
+                                self.values.strokes.pop();
+                            }
+                            if ui.add_enabled(!self.values.strokes.is_empty(), Button::new("Clear")).clicked() {
+                                self.values.strokes.clear();
+                            }
+                        });
+                        ui.end_row();
+                        // Save for when we reselect strokes
+                        self.values.path_strokes = PathCreator::Strokes { width: *width };
+                    });
+                ui.end_row();
+                ui.label("");
+                let hint = RichText::new("Drag over the image to draw, or click for corners and double click to finish");
+                ui.add(Label::new(hint.small()).wrap());
+                ui.end_row();
+            }
+            PathCreator::Segments { ref mut size, ref mut compactness, ref mut order } => {
+                ui.label("");
+                egui::Grid::new(format!("path_options_grid_{}", id))
+                    .num_columns(2)
+                    .min_row_height(25.0)
+                    .show(ui, |ui| {
+                        ui.label(important_text("Segment Size"));
+                        let slider = egui::Slider::new(size, 4..=500)
+                            .logarithmic(true)
+                            .suffix("px")
+                            .clamping(SliderClamping::Never)
+                            .drag_value_speed(0.2)
+                            .smart_aim(false);
+                        ui.add(slider);
+                        ui.end_row();
+                        ui.label(important_text("Compactness"));
+                        let slider = egui::Slider::new(compactness, 0.1..=100.0)
+                            .logarithmic(true)
+                            .clamping(SliderClamping::Never)
+                            .max_decimals(1);
+                        ui.add(slider);
+                        ui.end_row();
+                        ui.label(important_text("Order"));
+                        region_order_buttons(ui, order);
+                        ui.end_row();
+                        // Save for when we reselect segments
+                        self.values.path_segments = PathCreator::Segments { size: *size, compactness: *compactness, order: *order };
+                    });
+                ui.end_row();
+            }
+            PathCreator::Curve { ref mut function, ref mut offset, ref mut width, ref mut scale } => {
+                ui.label("");
+                egui::Grid::new(format!("path_options_grid_{}", id))
+                    .num_columns(2)
+                    .min_row_height(25.0)
+                    .show(ui, |ui| {
+                        ui.label(important_text("Function"));
+                        ui.horizontal(|ui| {
+                            let formula = match function {
+                                CurveFunction::Expression(f) => *f,
+                                _ => self.values.path_curve_formula,
+                            };
+                            for (f, name) in [
+                                (CurveFunction::Atan, "x³"),
+                                (CurveFunction::Sine, "Sine"),
+                                (CurveFunction::Parabola, "Parabola"),
+                                (CurveFunction::Expression(formula), "Formula"),
+                            ] {
+                                ui.selectable_value(function, f, name);
+                            }
+                        });
+                        ui.end_row();
+                        if let CurveFunction::Expression(formula) = function {
+                            ui.label(important_text("f(t) ="));
+                            let mut text = formula.as_str().to_string();
+                            // Longer texts don't fit into a formula and are ignored
+                            if ui.text_edit_singleline(&mut text).changed() {
+                                if let Some(f) = Formula::new(&text) {
+                                    *formula = f;
+                                }
+                            }
+                            ui.end_row();
+                            if let Err(e) = formula.eval(0.0) {
+                                ui.label("");
+                                ui.label(RichText::new(e).italics().color(Color32::LIGHT_RED));
+                                ui.end_row();
+                            }
+                            self.values.path_curve_formula = *formula;
+                        }
+                        let slider_a = egui::Slider::new(offset, -h2..=h2)
                             .clamping(SliderClamping::Never)
                             .drag_value_speed(0.2)
                             .max_decimals(1);
-                            let slider_w = egui::Slider::new(scalar_w, -500..=500)
+                        let slider_w = egui::Slider::new(width, -500.0..=500.0)
                             .clamping(SliderClamping::Never)
                             .drag_value_speed(0.2)
                             .max_decimals(1);
-                        let slider_l = egui::Slider::new(scalar_l, 0.0..=20.0)
+                        let slider_l = egui::Slider::new(scale, 0.0..=20.0)
                             .clamping(SliderClamping::Never)
                             .drag_value_speed(0.2)
                             .max_decimals(1);
@@ -106,11 +684,16 @@ impl PixelsorterGui {
                         ui.label(important_text("Width"));
                         ui.add(slider_w);
                         ui.end_row();
-                        ui.label(important_text("Slope"));
+                        ui.label(important_text("Scale"));
                         ui.add(slider_l);
                         ui.end_row();
-                        // Save for when we reselect diagonally
-                        self.values.path_x3 = current_path;
+                        // Save for when we reselect the curve
+                        self.values.path_curve = PathCreator::Curve {
+                            function: *function,
+                            offset: *offset,
+                            width: *width,
+                            scale: *scale,
+                        };
                     });
                 ui.end_row();
             }
@@ -125,6 +708,9 @@ impl PixelsorterGui {
                 (self.values.selector_fixed, "Fixed"),
                 (self.values.selector_random, "Random"),
                 (self.values.selector_thres, "Threshold"),
+                (self.values.selector_hysteresis, "Hysteresis"),
+                (self.values.selector_edges, "Edges"),
+                (self.values.selector_noise, "Noise"),
             ] {
                 ui.selectable_value(&mut self.values.selector, s, n);
             }
@@ -150,14 +736,48 @@ impl PixelsorterGui {
                         // Save selector state
                         self.values.selector_fixed = self.values.selector;
                     }
-                    PixelSelector::Random { max } => {
+                    PixelSelector::Random { min, max, distribution, variation } => {
+                        ui.label(important_text("Min"));
+                        let slider = egui::Slider::new(min, 1..=2000)
+                            .logarithmic(true)
+                            .clamping(SliderClamping::Never)
+                            .drag_value_speed(0.2)
+                            .smart_aim(false)
+                            .step_by(1.0);
+                        if ui.add(slider).dragged() {
+                            *max = (*max).max(*min);
+                        };
+                        ui.end_row();
                         ui.label(important_text("Max"));
-                        let slider = egui::Slider::new(max, 0..=2000)
+                        let slider = egui::Slider::new(max, 1..=2000)
                             .logarithmic(true)
                             .clamping(SliderClamping::Never)
                             .drag_value_speed(0.2)
                             .smart_aim(false)
                             .step_by(1.0);
+                        if ui.add(slider).dragged() {
+                            *min = (*min).min(*max);
+                        };
+                        ui.end_row();
+                        ui.label(important_text("Distribution"));
+                        ui.horizontal(|ui| {
+                            vec![
+                                RandomDistribution::Uniform,
+                                RandomDistribution::Normal,
+                                RandomDistribution::Exponential,
+                                RandomDistribution::Perlin,
+                            ]
+                            .into_iter()
+                            .for_each(|d| {
+                                ui.selectable_value(distribution, d, format!("{:?}", d));
+                            });
+                        });
+                        ui.end_row();
+                        ui.label(important_text("Variation"));
+                        let slider = egui::Slider::new(variation, 0..=100)
+                            .suffix("%")
+                            .drag_value_speed(0.2)
+                            .smart_aim(false);
                         ui.add(slider);
                         ui.end_row();
                         // Save selector state
@@ -178,73 +798,96 @@ impl PixelsorterGui {
                         });
                         ui.end_row();
 
-                        let (cap, selector_suffix) = if *criteria == PixelSelectCriteria::Hue {
-                            (360, "°")
-                        } else {
-                            (256, "")
-                        };
-
-                        // Get slider colors and image
-                        // HSVA::new(hue, saturation, brightness, alpha)
-                        let (mincol, maxcol, criteria_image) = match criteria {
-                            PixelSelectCriteria::Hue => (
-                                Hsva::new(*min as f32 / 360.0, 1.0, 1.0, 1.0).into(),
-                                Hsva::new(*max as f32 / 360.0, 1.0, 1.0, 1.0).into(),
-                                Image::new(egui::include_image!("../../assets/hue-bar.png")),
-                            ),
-                            PixelSelectCriteria::Brightness => (
-                                Hsva::new(1.0, 0.0, *min as f32 / 256.0, 1.0).into(),
-                                Hsva::new(1.0, 0.0, *max as f32 / 256.0, 1.0).into(),
-                                Image::new(egui::include_image!("../../assets/brightness-bar.png")),
-                            ),
-                            PixelSelectCriteria::Saturation => (
-                                Hsva::new(1.0, *min as f32 / 256.0, 1.0, 1.0).into(),
-                                Hsva::new(1.0, *max as f32 / 256.0, 1.0, 1.0).into(),
-                                Image::new(egui::include_image!("../../assets/saturation-bar.png")),
-                            ),
-                        };
+                        criteria_range_sliders(ui, min, max, *criteria, "Min", "Max");
 
-                        ui.label(important_text("Min"));
-                        ui.scope(|ui| {
-                            ui.style_mut().visuals.selection.bg_fill = mincol;
-                            let min_slider = egui::Slider::new(min, 0..=cap)
-                                .handle_shape(HandleShape::Rect { aspect_ratio: 0.5 })
-                                .trailing_fill(true)
-                                .suffix(selector_suffix)
-                                .drag_value_speed(0.2)
-                                .smart_aim(false);
-                            if ui.add(min_slider).dragged() {
-                                *max = (*max).clamp(*min, u64::MAX);
-                            };
+                        // Suggest thresholds from the statistics of the original image
+                        ui.label(important_text("Auto"));
+                        ui.add_enabled_ui(self.layered_sorter.is_some(), |ui| {
+                            ui.horizontal(|ui| {
+                                for (method, name) in [
+                                    (ThresholdSuggestion::Otsu, "Otsu"),
+                                    (ThresholdSuggestion::Percentile(20.0, 80.0), "20-80%"),
+                                    (ThresholdSuggestion::Top(10.0), "Top 10%"),
+                                ] {
+                                    if ui.button(name).clicked() {
+                                        if let Some(ls) = &self.layered_sorter {
+                                            (*min, *max) = analysis::suggest_threshold(ls.get_base_img(), *criteria, method);
+                                        }
+                                    }
+                                }
+                            });
                         });
                         ui.end_row();
 
-                        ui.label("");
-                        ui.add(
-                            criteria_image
-                                .maintain_aspect_ratio(false)
-                                .fit_to_exact_size([ui.style().spacing.slider_width, 15.0].into()),
-                        );
+                        // Save selector state
+                        self.values.selector_thres = self.values.selector;
+                    }
+                    PixelSelector::Hysteresis { start_min, start_max, min, max, criteria } => {
+                        ui.label(important_text("Criteria"));
+                        ui.horizontal(|ui| {
+                            vec![
+                                PixelSelectCriteria::Hue,
+                                PixelSelectCriteria::Brightness,
+                                PixelSelectCriteria::Saturation,
+                            ]
+                            .into_iter()
+                            .for_each(|c| {
+                                ui.selectable_value(criteria, c, format!("{:?}", c));
+                            });
+                        });
                         ui.end_row();
 
-                        ui.label(important_text("Max"));
-                        ui.scope(|ui| {
-                            ui.style_mut().visuals.selection.bg_fill = maxcol;
-                            let max_slider = egui::Slider::new(max, 0..=cap)
-                                .handle_shape(HandleShape::Rect { aspect_ratio: 0.5 })
-                                .trailing_fill(true)
-                                .suffix(selector_suffix)
-                                .drag_value_speed(0.2)
-                                .smart_aim(false);
+                        criteria_range_sliders(ui, start_min, start_max, *criteria, "Start Min", "Start Max");
+                        criteria_range_sliders(ui, min, max, *criteria, "Min", "Max");
 
-                            if ui.add(max_slider).dragged() {
-                                *min = (*min).clamp(u64::MIN, *max);
-                            };
+                        // Save selector state
+                        self.values.selector_hysteresis = self.values.selector;
+                    }
+                    PixelSelector::Edges { threshold } => {
+                        ui.label(important_text("Threshold"));
+                        let slider = egui::Slider::new(threshold, 0..=255)
+                            .drag_value_speed(0.2)
+                            .smart_aim(false);
+                        ui.add(slider);
+                        ui.end_row();
+                        // Save selector state
+                        self.values.selector_edges = self.values.selector;
+                    }
+                    PixelSelector::Noise { kind, seed, scale, octaves, threshold } => {
+                        ui.label(important_text("Noise"));
+                        ui.horizontal(|ui| {
+                            vec![NoiseKind::Perlin, NoiseKind::Simplex, NoiseKind::Worley]
+                                .into_iter()
+                                .for_each(|k| {
+                                    ui.selectable_value(kind, k, format!("{:?}", k));
+                                });
+                        });
+                        ui.end_row();
+                        ui.label(important_text("Threshold"));
+                        ui.add(egui::Slider::new(threshold, 0..=255).drag_value_speed(0.2).smart_aim(false));
+                        ui.end_row();
+                        ui.label(important_text("Scale"));
+                        let slider = egui::Slider::new(scale, 1..=1000)
+                            .logarithmic(true)
+                            .clamping(SliderClamping::Never)
+                            .suffix("px")
+                            .drag_value_speed(0.2)
+                            .smart_aim(false);
+                        ui.add(slider);
+                        ui.end_row();
+                        ui.label(important_text("Octaves"));
+                        ui.add(egui::Slider::new(octaves, 1..=8));
+                        ui.end_row();
+                        ui.label(important_text("Seed"));
+                        ui.horizontal(|ui| {
+                            ui.add(egui::DragValue::new(seed));
+                            if ui.button("Randomize").clicked() {
+                                *seed = rand::random();
+                            }
                         });
                         ui.end_row();
-
                         // Save selector state
-                        self.values.selector_thres = self.values.selector;
+                        self.values.selector_noise = self.values.selector;
                     }
                     // We don't expose the Full Selector to the gui, so I don't wanna support it
                     PixelSelector::Full => {
@@ -270,20 +913,63 @@ impl PixelsorterGui {
     }
 
     pub(super) fn algorithmn_combo_box(&mut self, ui: &mut Ui, id: u64) {
-        egui::ComboBox::from_id_salt(format!("algorithm_combo_{}", id))
-            .selected_text(format!("{:?}", self.values.algorithm))
-            .show_ui(ui, |ui| {
-                vec![
-                    SortingAlgorithm::Mapsort,
-                    SortingAlgorithm::Shellsort,
-                    SortingAlgorithm::Glitchsort,
-                    SortingAlgorithm::DebugColor,
-                ]
-                .into_iter()
-                .for_each(|a| {
-                    ui.selectable_value(&mut self.values.algorithm, a, format!("{:?}", a));
+        let name = |a: SortingAlgorithm| match a {
+            SortingAlgorithm::Rotate(_) => "Rotate".to_string(),
+            SortingAlgorithm::Stablesort(_) => "Stablesort".to_string(),
+            SortingAlgorithm::DebugColor(c) => format!("DebugColor by {:?}", c),
+            _ => format!("{:?}", a),
+        };
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt(format!("algorithm_combo_{}", id))
+                .selected_text(name(self.values.algorithm))
+                .show_ui(ui, |ui| {
+                    vec![
+                        SortingAlgorithm::Mapsort,
+                        SortingAlgorithm::Shellsort,
+                        SortingAlgorithm::Glitchsort,
+                        SortingAlgorithm::Stablesort(self.values.algorithm_ties),
+                        SortingAlgorithm::DebugColor(DebugColoring::Start),
+                        SortingAlgorithm::DebugColor(DebugColoring::Length),
+                        SortingAlgorithm::DebugColor(DebugColoring::Index),
+                    ]
+                    .into_iter()
+                    .for_each(|a| {
+                        ui.selectable_value(&mut self.values.algorithm, a, name(a));
+                    });
+                    ui.separator();
+                    ui.label("Span effects");
+                    vec![
+                        SortingAlgorithm::Reverse,
+                        SortingAlgorithm::Shuffle,
+                        SortingAlgorithm::Smear,
+                        SortingAlgorithm::Average,
+                        SortingAlgorithm::Gradient,
+                        SortingAlgorithm::Rotate(self.values.algorithm_rotate_val),
+                    ]
+                    .into_iter()
+                    .for_each(|a| {
+                        ui.selectable_value(&mut self.values.algorithm, a, name(a));
+                    });
                 });
-            });
+            match self.values.algorithm {
+                SortingAlgorithm::Rotate(_) => {
+                    ui.label("by");
+                    ui.add(egui::DragValue::new(&mut self.values.algorithm_rotate_val).suffix(" px"));
+                    self.values.algorithm = SortingAlgorithm::Rotate(self.values.algorithm_rotate_val);
+                }
+                SortingAlgorithm::Stablesort(_) => {
+                    egui::ComboBox::from_id_salt(format!("ties_combo_{}", id))
+                        .selected_text(format!("{:?} ties", self.values.algorithm_ties))
+                        .show_ui(ui, |ui| {
+                            for t in [TiePolicy::Keep, TiePolicy::Reverse, TiePolicy::Random] {
+                                ui.selectable_value(&mut self.values.algorithm_ties, t, format!("{:?} ties", t));
+                            }
+                        });
+                    self.values.algorithm = SortingAlgorithm::Stablesort(self.values.algorithm_ties);
+                }
+                _ => {}
+            }
+        });
     }
 
     pub(super) fn sorting_options_panel(&mut self, ui: &mut Ui, id: u64) {
@@ -323,9 +1009,43 @@ impl PixelsorterGui {
                     self.criteria_combo_box(ui, id);
                 });
                 ui.end_row();
+                // KEY SOURCE
+                ui.label(important_text("Keys from"));
+                self.key_source_buttons(ui);
+                ui.end_row();
+                // KEY PRECISION
+                ui.label(important_text("Precision"));
+                ui.checkbox(&mut self.values.quantize, "Integer keys")
+                    .on_hover_text("Round hue, saturation and brightness down to integers before sorting");
+                ui.end_row();
             });
     }
 
+    /// Choose between the pixels and a guide image to read the sort keys from
+    pub(super) fn key_source_buttons(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            if ui.selectable_label(self.values.key_source == KeySource::Pixels, "Pixels").clicked() {
+                self.values.key_source = KeySource::Pixels;
+            }
+            let label = match &self.values.key_source {
+                KeySource::Image(img) => format!("Guide image ({} x {})", img.width(), img.height()),
+                KeySource::Pixels => "Guide image...".into(),
+            };
+            let is_image = matches!(self.values.key_source, KeySource::Image(_));
+            if ui.selectable_label(is_image, label).on_hover_text("Open an image (f.e. a depth map) to sort by").clicked() {
+                let file = rfd::FileDialog::new()
+                    .add_filter("Images", &["png", "jpg", "jpeg", "webp"])
+                    .pick_file();
+                if let Some(f) = file {
+                    match image::open(f.as_path()) {
+                        Ok(img) => self.values.key_source = KeySource::Image(Arc::new(img.into_rgb8())),
+                        Err(e) => warn!("Could not open the guide image {}: {}", f.to_string_lossy(), e),
+                    }
+                }
+            }
+        });
+    }
+
     pub(super) fn save_options_panel(&mut self, ui: &mut Ui) {
         ui.horizontal(|ui| {
             ui.set_width(full_width(&ui));
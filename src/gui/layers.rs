@@ -1,5 +1,4 @@
 use image::RgbImage;
-use pixelsortery::Pixelsorter;
 
 use super::PixelsorterValues;
 
@@ -208,7 +207,8 @@ impl SortingLayer {
 
     fn sort(&mut self, img: &RgbImage) {
         let mut sorted_img = img.clone();
-        self.sorting_values.to_pixelsorter().sort(&mut sorted_img);
+        let ps = self.sorting_values.to_pixelsorter();
+        ps.sort(&mut sorted_img);
         self.sorted_img = sorted_img;
         self.needs_sorting = false;
     }
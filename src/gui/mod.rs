@@ -1,6 +1,6 @@
 #![allow(unused)]
 use eframe::egui::{
-    self, Align, Button, Color32, Image, Key, Layout, Modifiers, RichText, ScrollArea,
+    self, Align, Button, Color32, Image, Key, Layout, Modifiers, RichText, ScrollArea, Sense,
     TextureFilter, TextureHandle, TextureOptions, Ui, Vec2,
 };
 use egui::{scroll_area::ScrollBarVisibility, style::ScrollStyle, ColorImage, Hyperlink, Modal, Rgba};
@@ -12,12 +12,16 @@ use inflections::case::to_title_case;
 use layers::LayeredSorter;
 use log::{info, warn};
 use pixelsortery::{
-    path_creator::PathCreator,
+    path_creator::{
+        CurveFunction, FlowSource, Formula, PathCreator, RegionOrder, RoundOptions, Stroke, TileDirection, TileDirections,
+        TileOrder,
+    },
     pixel_selector::{
         PixelSelectCriteria,
         PixelSelector::{self, *},
+        NoiseKind, RandomDistribution,
     },
-    span_sorter::{SortingAlgorithm, SortingCriteria},
+    span_sorter::{KeySource, SortingAlgorithm, SortingCriteria, TiePolicy},
     Pixelsorter,
 };
 #[cfg(feature = "video")]
@@ -80,6 +84,8 @@ struct PixelsorterGui {
     layered_sorter: Option<LayeredSorter>,
     /// All the adjustable values for the pixelsorter
     values: PixelsorterValues,
+    /// The stroke that is being drawn right now
+    drawing: Option<Stroke>,
     show_mask: bool,
     /// The current image from the selected layer
     img: Option<RgbImage>,
@@ -105,20 +111,50 @@ struct PixelsorterGui {
 }
 
 /// Adjustable components of the pixelsorter, remembers values like diagonal angle
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, PartialEq)]
 struct PixelsorterValues {
     reverse: bool,
+    /// Sort in polar coordinates around the center of `path_round`
+    polar: bool,
     path: PathCreator,
     selector: PixelSelector,
     criteria: SortingCriteria,
     algorithm: SortingAlgorithm,
+    /// Round the keys down to integers
+    quantize: bool,
+    /// Where the sort keys are read from, pixels or a guide image
+    key_source: KeySource,
+    /// The drawn strokes, used by the stroke path
+    strokes: Vec<Stroke>,
     // Values that may not be set right now, but the values should be remembered
     /// We can select these with the real structs tbh
     path_diagonally_val: f32,
-    path_x3: PathCreator,
+    path_rotated_val: f32,
+    /// Shift of the rotate span effect
+    algorithm_rotate_val: i32,
+    /// Tie policy of the stable sort
+    algorithm_ties: TiePolicy,
+    path_round: RoundOptions,
+    path_curve: PathCreator,
+    /// Keeps the formula around while another curve function is selected
+    path_curve_formula: Formula,
+    path_hilbert: PathCreator,
+    path_waves: PathCreator,
+    path_polygon: PathCreator,
+    path_star: PathCreator,
+    path_flow_image: PathCreator,
+    path_flow_noise: PathCreator,
+    path_voronoi: PathCreator,
+    path_segments: PathCreator,
+    path_strokes: PathCreator,
+    path_tiles: PathCreator,
+    path_walk: PathCreator,
     selector_random: PixelSelector,
     selector_fixed: PixelSelector,
     selector_thres: PixelSelector,
+    selector_hysteresis: PixelSelector,
+    selector_edges: PixelSelector,
+    selector_noise: PixelSelector,
 }
 
 #[derive(PartialEq)]
@@ -137,7 +173,11 @@ impl PixelsorterValues {
         ps.selector = self.selector;
         ps.sorter.criteria = self.criteria;
         ps.sorter.algorithm = self.algorithm;
+        ps.sorter.quantize = self.quantize;
+        ps.sorter.key_source = self.key_source.clone();
+        ps.strokes = self.strokes.clone();
         ps.reverse = self.reverse;
+        ps.polar = self.polar.then_some(self.path_round);
         ps
     }
 
@@ -146,17 +186,52 @@ impl PixelsorterValues {
         self.selector = ps.selector;
         self.criteria = ps.sorter.criteria;
         self.algorithm = ps.sorter.algorithm;
+        self.quantize = ps.sorter.quantize;
+        self.key_source = ps.sorter.key_source.clone();
+        self.strokes = ps.strokes.clone();
         self.reverse = ps.reverse;
+        self.polar = ps.polar.is_some();
+        match self.algorithm {
+            SortingAlgorithm::Rotate(shift) => self.algorithm_rotate_val = shift,
+            SortingAlgorithm::Stablesort(ties) => self.algorithm_ties = ties,
+            _ => {}
+        }
+        if let Some(o) = ps.polar {
+            self.path_round = o;
+        }
         // Set the saved value, just in case
-        if let PathCreator::Diagonally(a) = self.path {
-            self.path_diagonally_val = a;
+        match self.path {
+            PathCreator::Diagonally(a) => self.path_diagonally_val = a,
+            PathCreator::Rotated(a) => self.path_rotated_val = a,
+            PathCreator::Rays(o) | PathCreator::Circles(o) | PathCreator::Spiral(o) => self.path_round = o,
+            PathCreator::Hilbert { .. } => self.path_hilbert = self.path,
+            PathCreator::Curve { function, .. } => {
+                self.path_curve = self.path;
+                if let CurveFunction::Expression(formula) = function {
+                    self.path_curve_formula = formula;
+                }
+            }
+            PathCreator::Waves { .. } => self.path_waves = self.path,
+            PathCreator::Polygon { .. } => self.path_polygon = self.path,
+            PathCreator::Star { .. } => self.path_star = self.path,
+            PathCreator::Flow(FlowSource::Image { .. }) => self.path_flow_image = self.path,
+            PathCreator::Flow(FlowSource::Noise { .. }) => self.path_flow_noise = self.path,
+            PathCreator::Voronoi { .. } => self.path_voronoi = self.path,
+            PathCreator::Segments { .. } => self.path_segments = self.path,
+            PathCreator::Strokes { .. } => self.path_strokes = self.path,
+            PathCreator::Tiles { .. } => self.path_tiles = self.path,
+            PathCreator::RandomWalk { .. } => self.path_walk = self.path,
+            _ => {}
         }
         match self.selector {
             Fixed { len } => self.selector_fixed = Fixed { len },
-            Random { max } => self.selector_random = Random { max },
+            Random { .. } => self.selector_random = self.selector,
             Threshold { min, max, criteria } => {
                 self.selector_thres = Threshold { min, max, criteria }
             }
+            Hysteresis { .. } => self.selector_hysteresis = self.selector,
+            Edges { threshold } => self.selector_edges = Edges { threshold },
+            Noise { .. } => self.selector_noise = self.selector,
             Full => warn!("The gui doesn't support the Full-Selector. Just because."),
         }
     }
@@ -170,8 +245,10 @@ impl Default for PixelsorterGui {
             img: None,
             texture: None,
             show_mask: false,
+            drawing: None,
             values: PixelsorterValues {
                 reverse: false,
+                polar: false,
                 path: PathCreator::VerticalLines,
                 criteria: SortingCriteria::Brightness,
                 selector: PixelSelector::Threshold {
@@ -180,16 +257,59 @@ impl Default for PixelsorterGui {
                     criteria: PixelSelectCriteria::Hue,
                 },
                 algorithm: SortingAlgorithm::Shellsort,
+                quantize: false,
+                key_source: KeySource::Pixels,
+                strokes: Vec::new(),
 
                 path_diagonally_val: 45.0,
-                path_x3: PathCreator::X3(0, 300, 10.0),
-                selector_random: PixelSelector::Random { max: 30 },
+                path_rotated_val: 30.0,
+                algorithm_rotate_val: 10,
+                algorithm_ties: TiePolicy::Keep,
+                path_round: RoundOptions::default(),
+                path_curve: PathCreator::Curve { function: CurveFunction::Atan, offset: 0, width: 300.0, scale: 10.0 },
+                path_curve_formula: Formula::new("sin(t * pi) * t").unwrap(),
+                path_hilbert: PathCreator::Hilbert { block_size: None, alternate: false },
+                path_waves: PathCreator::Waves { amplitude: 20.0, wavelength: 200.0, phase: 0.0, angle: 0.0 },
+                path_polygon: PathCreator::Polygon { sides: 6, rotation: 0.0, options: RoundOptions::default() },
+                path_star: PathCreator::Star { points: 5, inner: 0.5, rotation: 0.0, options: RoundOptions::default() },
+                path_flow_image: PathCreator::Flow(FlowSource::Image { smoothing: 4 }),
+                path_flow_noise: PathCreator::Flow(FlowSource::Noise { scale: 128, seed: 0 }),
+                path_voronoi: PathCreator::Voronoi { size: 100, jitter: 1.0, order: RegionOrder::Rows, seed: 0 },
+                path_segments: PathCreator::Segments { size: 40, compactness: 10.0, order: RegionOrder::Rows },
+                path_strokes: PathCreator::Strokes { width: 20 },
+                path_tiles: PathCreator::Tiles {
+                    size: 100,
+                    directions: TileDirections::new(&[TileDirection::Up, TileDirection::Right]).unwrap(),
+                    order: TileOrder::Cycle,
+                },
+                path_walk: PathCreator::RandomWalk { angle: 90.0, bias: 1.0, seed: 0 },
+                selector_random: PixelSelector::Random {
+                    min: 1,
+                    max: 30,
+                    distribution: RandomDistribution::Uniform,
+                    variation: 0,
+                },
                 selector_fixed: PixelSelector::Fixed { len: 100 },
                 selector_thres: PixelSelector::Threshold {
                     min: 0,
                     max: 360,
                     criteria: PixelSelectCriteria::Brightness,
                 },
+                selector_hysteresis: PixelSelector::Hysteresis {
+                    start_min: 160,
+                    start_max: 255,
+                    min: 80,
+                    max: 255,
+                    criteria: PixelSelectCriteria::Brightness,
+                },
+                selector_edges: PixelSelector::Edges { threshold: 60 },
+                selector_noise: PixelSelector::Noise {
+                    kind: NoiseKind::Perlin,
+                    seed: 0,
+                    scale: 64,
+                    octaves: 1,
+                    threshold: 128,
+                },
             },
             time_last_sort: Arc::new(Mutex::new(Duration::default())),
             auto_sort: true,
@@ -214,11 +334,7 @@ impl Default for PixelsorterGui {
 impl PixelsorterGui {
     // Given a pixelsorter, return a PixelsorterGui with the values set
     fn with_values(mut self, ps: &Pixelsorter) -> Self {
-        self.values.path = ps.path_creator;
-        self.values.criteria = ps.sorter.criteria;
-        self.values.algorithm = ps.sorter.algorithm;
-        self.values.reverse = ps.reverse;
-        self.values.selector = ps.selector;
+        self.values.read_from_pixelsorter(ps);
         self
     }
     fn with_image(mut self, img: RgbImage, image_path: PathBuf) -> Self {
@@ -231,10 +347,10 @@ impl PixelsorterGui {
     fn sort_img(&mut self, ctx: &egui::Context, force: bool) {
         if let Some(ls) = &mut self.layered_sorter {
             // values.selector is not up-to-date with the current layer as it seems
-            if selector_is_threshold(ls.get_current_layer().get_sorting_values().selector)
+            if selector_has_mask(ls.get_current_layer().get_sorting_values().selector)
                 && self.show_mask
             {
-                // We can unwrap here, because the mask() function only fails if we don't have a threshold selector
+                // We can unwrap here, because the mask() function only fails if we don't have a threshold, hysteresis, edges or noise selector
                 self.img = Some(ls.get_mask_for_current_layer().unwrap().clone());
             } else {
                 let timestart = Instant::now();
@@ -273,13 +389,122 @@ impl PixelsorterGui {
     }
 
     /// Tries to show the image if it exists
-    fn show_img(&self, ui: &mut Ui) {
+    fn show_img(&mut self, ui: &mut Ui) {
         if let Some(tex) = &self.texture {
-            egui::Frame::group(ui.style_mut())
+            let drawing_strokes = matches!(self.values.path, PathCreator::Strokes { .. });
+            let sense = if drawing_strokes { Sense::click_and_drag() } else { Sense::click() };
+            let img_width = tex.size()[0] as f32;
+            let response = egui::Frame::group(ui.style_mut())
                 .inner_margin(0)
                 .show(ui, |ui| {
-                    ui.add(Image::new((tex.id(), tex.size_vec2())).shrink_to_fit());
-            });
+                    ui.add(Image::new((tex.id(), tex.size_vec2())).shrink_to_fit().sense(sense))
+            }).inner;
+
+            if drawing_strokes {
+                self.stroke_tool(ui, &response, img_width);
+                return;
+            }
+
+            // Place the center of round paths (or of the polar coordinates) where the image was clicked
+            if let Some(pos) = response.interact_pointer_pos().filter(|_| response.clicked()) {
+                let rel = (pos - response.rect.min) / response.rect.size();
+                let center = (rel.x.clamp(0.0, 1.0), rel.y.clamp(0.0, 1.0));
+                let moved = match &mut self.values.path {
+                    PathCreator::Rays(o) | PathCreator::Circles(o) | PathCreator::Spiral(o) => {
+                        o.center = center;
+                        self.values.path_round = *o;
+                        true
+                    }
+                    PathCreator::Polygon { options, .. } => {
+                        options.center = center;
+                        self.values.path_polygon = self.values.path;
+                        true
+                    }
+                    PathCreator::Star { options, .. } => {
+                        options.center = center;
+                        self.values.path_star = self.values.path;
+                        true
+                    }
+                    _ if self.values.polar => {
+                        self.values.path_round.center = center;
+                        true
+                    }
+                    _ => false,
+                };
+                if moved {
+                    // The values get reloaded from the layer every frame, so write them back right away
+                    if let Some(ls) = &mut self.layered_sorter {
+                        ls.update_current(self.values.clone());
+                    }
+                    // The change check already happened this frame, so resort in the next one
+                    if self.auto_sort {
+                        self.do_sort = true;
+                    }
+                    ui.ctx().request_repaint();
+                }
+            }
+        }
+    }
+
+    /// Draws strokes for the stroke path over the image. Dragging draws a freehand stroke,
+    /// clicking adds corners to a polyline, which is finished with a double click or enter (escape cancels it)
+    fn stroke_tool(&mut self, ui: &mut Ui, response: &egui::Response, img_width: f32) {
+        let rect = response.rect;
+        let to_rel = |pos: egui::Pos2| {
+            let rel = (pos - rect.min) / rect.size();
+            (rel.x.clamp(0.0, 1.0), rel.y.clamp(0.0, 1.0))
+        };
+        let to_screen = |&(x, y): &(f32, f32)| rect.min + egui::vec2(x * rect.width(), y * rect.height());
+
+        if let Some(pos) = response.interact_pointer_pos() {
+            if response.drag_started() || response.dragged() || response.clicked() {
+                let stroke = self.drawing.get_or_insert_with(Vec::new);
+                // Skip points that are too close to the last one
+                if stroke.last().map_or(true, |last| to_screen(last).distance(pos) > 2.0) {
+                    stroke.push(to_rel(pos));
+                }
+            }
+        }
+        let (enter, escape) = ui.input(|i| (i.key_pressed(Key::Enter), i.key_pressed(Key::Escape)));
+        if escape {
+            self.drawing = None;
+        }
+        if response.drag_stopped() || response.double_clicked() || enter {
+            if let Some(stroke) = self.drawing.take() {
+                self.values.strokes.push(stroke);
+                // The values get reloaded from the layer every frame, so write them back right away
+                if let Some(ls) = &mut self.layered_sorter {
+                    ls.update_current(self.values.clone());
+                }
+                // The change check already happened this frame, so resort in the next one
+                if self.auto_sort {
+                    self.do_sort = true;
+                }
+                ui.ctx().request_repaint();
+            }
+        }
+
+        // Only show the strokes while drawing, so they don't hide the sorted image
+        let PathCreator::Strokes { width } = self.values.path else { return };
+        if !response.hovered() && self.drawing.is_none() {
+            return;
+        }
+        let mut drawing = self.drawing.clone();
+        // Preview the next corner of a polyline
+        if let (Some(stroke), Some(hover)) = (&mut drawing, response.hover_pos()) {
+            stroke.push(to_rel(hover));
+        }
+        let painter = ui.painter_at(rect);
+        let brush = egui::Stroke::new(width as f32 * rect.width() / img_width, Color32::from_white_alpha(40));
+        let line = egui::Stroke::new(1.0, Color32::WHITE);
+        for stroke in self.values.strokes.iter().chain(&drawing) {
+            let points: Vec<egui::Pos2> = stroke.iter().map(to_screen).collect();
+            if let [point] = points.as_slice() {
+                painter.circle(*point, brush.width / 2.0, brush.color, line);
+            } else {
+                painter.add(egui::Shape::line(points.clone(), brush));
+                painter.add(egui::Shape::line(points, line));
+            }
         }
     }
 
@@ -462,7 +687,7 @@ impl eframe::App for PixelsorterGui {
         } else {
             // Create a layering thingy if we don't have one yet
             if let Some(img) = &self.img {
-                self.layered_sorter = Some(LayeredSorter::new(img.clone(), self.values));
+                self.layered_sorter = Some(LayeredSorter::new(img.clone(), self.values.clone()));
             }
         }
 
@@ -595,7 +820,7 @@ impl eframe::App for PixelsorterGui {
                             });
                             let ui = &mut columns[2];
                             ui.add_enabled_ui(
-                                selector_is_threshold(self.values.selector),
+                                selector_has_mask(self.values.selector),
                                 |ui| {
                                     ui.checkbox(&mut self.show_mask, "Show mask");
                                 },
@@ -725,14 +950,14 @@ fn full_width(ui: &Ui) -> f32 {
 fn full_height(ui: &Ui) -> f32 {
     ui.max_rect().max.y - ui.max_rect().min.y
 }
-fn selector_is_threshold(sel: PixelSelector) -> bool {
+fn selector_has_mask(sel: PixelSelector) -> bool {
     matches!(
         sel,
         Threshold {
             min: _,
             max: _,
             criteria: _
-        }
+        } | Hysteresis { .. } | Edges { threshold: _ } | Noise { .. }
     )
 }
 
@@ -4,14 +4,19 @@ use image::{codecs::png::PngEncoder, GenericImageView, ImageResult, Rgb, RgbImag
 use log::{debug, error, info, warn};
 use path_creator::PathCreator;
 use rayon::prelude::*;
-use span_sorter::{SortingCriteria, SpanSorter};
+use span_sorter::{KeySource, SortingCriteria, SpanInfo, SpanSorter};
 use std::{any::Any, fmt::Debug, fs, io::{self, ErrorKind, Read, Write}, path::{Path, PathBuf}, process::{self, Command, Output, Stdio}, time::Instant};
 
 use crate::pixel_selector::PixelSelector;
 
+pub mod analysis;
 mod color_helpers;
+mod gradient;
+mod noise;
 pub mod path_creator;
 pub mod pixel_selector;
+mod polar;
+mod rotate;
 pub mod span_sorter;
 #[cfg(feature = "video")]
 mod video;
@@ -24,9 +29,15 @@ pub struct Pixelsorter {
     pub selector: PixelSelector,
     pub path_creator: path_creator::PathCreator,
     pub reverse: bool,
+    /// The strokes for `PathCreator::Strokes`
+    pub strokes: Vec<path_creator::Stroke>,
+    /// Sorts on the image unwrapped into polar coordinates around the center, if set
+    pub polar: Option<path_creator::RoundOptions>,
 }
 
 pub type Span = Vec<Rgb<u8>>;
+/// The pixels of a span (or a path), each one paired with its index (y * w + x) in the image
+pub type PixelSpan<'a> = Vec<(u64, &'a mut Rgb<u8>)>;
 
 impl Pixelsorter {
     // constructor
@@ -36,6 +47,8 @@ impl Pixelsorter {
             selector: PixelSelector::Full,
             path_creator: PathCreator::AllHorizontally,
             reverse: false,
+            strokes: Vec::new(),
+            polar: None,
         }
     }
     pub fn to_long_string(&self) -> String {
@@ -45,15 +58,50 @@ impl Pixelsorter {
             PathCreator::AllVertically => "All Vertically".into(),
             PathCreator::HorizontalLines => if self.reverse {"Left"} else {"Right"}.into(),
             PathCreator::VerticalLines => if self.reverse {"Up"} else {"Down"}.into(),
-            PathCreator::Circles => "Circles".into(),
-            PathCreator::Spiral => "Spiral".into(),
+            PathCreator::Circles(_) => "Circles".into(),
+            PathCreator::Spiral(_) => "Spiral".into(),
             PathCreator::SquareSpiral => "Square Spiral".into(),
             PathCreator::RectSpiral => "Rect Spiral".into(),
             PathCreator::Diagonally(a) => format!("Diagonally ({}°)", a),
-            PathCreator::Hilbert => "Hilbert Curve".into(),
+            PathCreator::Rotated(a) => format!("Rotated ({}°)", a),
+            PathCreator::Hilbert { block_size: None, .. } => "Hilbert Curve".into(),
+            PathCreator::Hilbert { block_size: Some(size), alternate } => {
+                format!("Hilbert Tiles ({}px{})", size, if alternate { ", alternating" } else { "" })
+            }
+            PathCreator::Waves { amplitude, wavelength, phase, angle } => {
+                format!("Waves ({}px, {}px, {}°, {}°)", amplitude, wavelength, phase, angle)
+            }
+            PathCreator::Polygon { sides, rotation, .. } => format!("Polygons ({} sides, {}°)", sides, rotation),
+            PathCreator::Star { points, inner, rotation, .. } => {
+                format!("Stars ({} points, {}, {}°)", points, inner, rotation)
+            }
+            PathCreator::Curve { function, offset, width, scale } => {
+                format!("Curve ({}, offset {}, width {}, scale {})", function, offset, width, scale)
+            }
+            PathCreator::Voronoi { size, jitter, order, seed } => {
+                format!("Voronoi ({}px, jitter {}, {:?}, seed {})", size, jitter, order, seed)
+            }
+            PathCreator::Segments { size, compactness, order } => {
+                format!("Segments ({}px, compactness {}, {:?})", size, compactness, order)
+            }
+            PathCreator::Strokes { width } => format!("Strokes ({}px, {} strokes)", width, self.strokes.len()),
+            PathCreator::RandomWalk { angle, bias, seed } => {
+                format!("Random Walk ({}°, bias {}, seed {})", angle, bias, seed)
+            }
+            PathCreator::Tiles { size, directions, order } => match order {
+                path_creator::TileOrder::Cycle => format!("Tiles ({}px, {})", size, directions),
+                path_creator::TileOrder::Random { seed } => format!("Random Tiles ({}px, {}, seed {})", size, directions, seed),
+            },
+            PathCreator::Flow(source) => match source {
+                path_creator::FlowSource::Image { smoothing } => format!("Image Flow (smoothing {}px)", smoothing),
+                path_creator::FlowSource::Noise { scale, seed } => format!("Noise Flow (scale {}, seed {})", scale, seed),
+            },
             p => format!("{}", p),
         }
         .as_str();
+        if self.polar.is_some() {
+            s += " (Polar)";
+        }
         s += "-";
         if self.reverse {
             s += "R-"
@@ -61,7 +109,9 @@ impl Pixelsorter {
         s += match self.selector {
             PixelSelector::Full => "Full".into(),
             PixelSelector::Fixed { len } => format!("Fixed length ({})", len),
-            PixelSelector::Random { max } => format!("Random length ({})", max),
+            PixelSelector::Random { min, max, distribution, variation } => {
+                format!("Random length ({}-{}, {:?}, ±{}%)", min, max, distribution, variation)
+            }
             PixelSelector::Threshold { min, max, criteria } => format!(
                 "{}{}-{}",
                 match criteria {
@@ -72,6 +122,23 @@ impl Pixelsorter {
                 min,
                 max
             ),
+            PixelSelector::Hysteresis { start_min, start_max, min, max, criteria } => format!(
+                "{}{}-{}({}-{})",
+                match criteria {
+                    pixel_selector::PixelSelectCriteria::Hue => "Hue",
+                    pixel_selector::PixelSelectCriteria::Brightness => "Brightness",
+                    pixel_selector::PixelSelectCriteria::Saturation => "Saturation",
+                },
+                start_min,
+                start_max,
+                min,
+                max
+            ),
+            PixelSelector::Edges { threshold } => format!("Edges ({})", threshold),
+            PixelSelector::Noise { kind, seed, scale, octaves, threshold } => format!(
+                "{:?} Noise ({}, scale {}, {} octaves, seed {})",
+                kind, threshold, scale, octaves, seed
+            ),
         }
         .as_str();
         s += "-";
@@ -79,14 +146,33 @@ impl Pixelsorter {
             span_sorter::SortingAlgorithm::Mapsort => "Mapsort",
             span_sorter::SortingAlgorithm::Shellsort => "Shellsort",
             span_sorter::SortingAlgorithm::Glitchsort => "Glitchsort",
-            span_sorter::SortingAlgorithm::DebugColor => "Debug-colors",
+            span_sorter::SortingAlgorithm::Stablesort(_) => "Stablesort",
+            span_sorter::SortingAlgorithm::DebugColor(_) => "Debug-colors",
+            span_sorter::SortingAlgorithm::Reverse => "Reverse",
+            span_sorter::SortingAlgorithm::Shuffle => "Shuffle",
+            span_sorter::SortingAlgorithm::Smear => "Smear",
+            span_sorter::SortingAlgorithm::Average => "Average",
+            span_sorter::SortingAlgorithm::Gradient => "Gradient",
+            span_sorter::SortingAlgorithm::Rotate(_) => "Rotate",
         };
+        match self.sorter.algorithm {
+            span_sorter::SortingAlgorithm::Rotate(shift) => s += &format!("({})", shift),
+            span_sorter::SortingAlgorithm::DebugColor(c) => s += &format!(" ({:?})", c),
+            span_sorter::SortingAlgorithm::Stablesort(ties) => s += &format!(" ({:?} ties)", ties),
+            _ => {}
+        }
         s += "-";
         s += match self.sorter.criteria {
             SortingCriteria::Hue => "Hue",
             SortingCriteria::Brightness => "Brightness",
             SortingCriteria::Saturation => "Saturation",
         };
+        if let KeySource::Image(_) = self.sorter.key_source {
+            s += " (Guide image)";
+        }
+        if self.sorter.quantize {
+            s += " (Integer keys)";
+        }
 
         s
     }
@@ -98,15 +184,33 @@ impl Pixelsorter {
             PathCreator::AllVertically => "Vertical".into(),
             PathCreator::HorizontalLines => if self.reverse {"Left"} else {"Right"}.into(),
             PathCreator::VerticalLines => if self.reverse {"Up"} else {"Down"}.into(),
-            PathCreator::Circles => "Circles".into(),
-            PathCreator::Spiral => "Spiral".into(),
+            PathCreator::Circles(_) => "Circles".into(),
+            PathCreator::Spiral(_) => "Spiral".into(),
             PathCreator::SquareSpiral => "Square".into(),
             PathCreator::RectSpiral => "Rect".into(),
             PathCreator::Diagonally(a) => format!("Diag({}°)", a),
-            PathCreator::Hilbert => "Hilbert".into(),
+            PathCreator::Rotated(a) => format!("Rot({}°)", a),
+            PathCreator::Hilbert { block_size: None, .. } => "Hilbert".into(),
+            PathCreator::Hilbert { block_size: Some(size), .. } => format!("Hilbert({})", size),
+            PathCreator::Moore => "Moore".into(),
+            PathCreator::Peano => "Peano".into(),
+            PathCreator::ZOrder => "Z-Order".into(),
+            PathCreator::Polygon { sides, .. } => format!("Polygon({})", sides),
+            PathCreator::Star { points, .. } => format!("Star({})", points),
+            PathCreator::Flow(source) => match source {
+                path_creator::FlowSource::Image { .. } => "Flow(Image)".into(),
+                path_creator::FlowSource::Noise { .. } => "Flow(Noise)".into(),
+            },
+            PathCreator::Voronoi { size, order, .. } => format!("Voronoi({} {:?})", size, order),
+            PathCreator::Segments { size, order, .. } => format!("Segments({} {:?})", size, order),
+            PathCreator::Tiles { size, directions, .. } => format!("Tiles({} {})", size, directions),
+            PathCreator::RandomWalk { angle, .. } => format!("Walk({}°)", angle),
             p => format!("{}", p),
         }
         .as_str();
+        if self.polar.is_some() {
+            s += "{P}"
+        };
         if self.reverse {
             s += "{R}"
         };
@@ -115,7 +219,7 @@ impl Pixelsorter {
         s += match self.selector {
             PixelSelector::Full => "Full".into(),
             PixelSelector::Fixed { len } => format!("Fixed ({})", len),
-            PixelSelector::Random { max } => format!("Random ({})", max),
+            PixelSelector::Random { min, max, distribution, .. } => format!("Random ({}-{} {:?})", min, max, distribution),
             PixelSelector::Threshold { min, max, criteria } => format!(
                 "{} ({}-{})",
                 match criteria {
@@ -126,6 +230,20 @@ impl Pixelsorter {
                 min,
                 max
             ),
+            PixelSelector::Hysteresis { start_min, start_max, min, max, criteria } => format!(
+                "{} ({}-{}/{}-{})",
+                match criteria {
+                    pixel_selector::PixelSelectCriteria::Hue => "Hue",
+                    pixel_selector::PixelSelectCriteria::Brightness => "Bright",
+                    pixel_selector::PixelSelectCriteria::Saturation => "Sat",
+                },
+                start_min,
+                start_max,
+                min,
+                max
+            ),
+            PixelSelector::Edges { threshold } => format!("Edges ({})", threshold),
+            PixelSelector::Noise { kind, threshold, .. } => format!("Noise ({:?} {})", kind, threshold),
         }
         .as_str();
         s += " | ";
@@ -133,14 +251,30 @@ impl Pixelsorter {
             span_sorter::SortingAlgorithm::Mapsort => "Map",
             span_sorter::SortingAlgorithm::Shellsort => "Shell",
             span_sorter::SortingAlgorithm::Glitchsort => "Glitch",
-            span_sorter::SortingAlgorithm::DebugColor => "Debug",
+            span_sorter::SortingAlgorithm::Stablesort(_) => "Stable",
+            span_sorter::SortingAlgorithm::DebugColor(_) => "Debug",
+            span_sorter::SortingAlgorithm::Reverse => "Reverse",
+            span_sorter::SortingAlgorithm::Shuffle => "Shuffle",
+            span_sorter::SortingAlgorithm::Smear => "Smear",
+            span_sorter::SortingAlgorithm::Average => "Average",
+            span_sorter::SortingAlgorithm::Gradient => "Gradient",
+            span_sorter::SortingAlgorithm::Rotate(_) => "Rotate",
         };
+        match self.sorter.algorithm {
+            span_sorter::SortingAlgorithm::Rotate(shift) => s += &format!(" {}", shift),
+            span_sorter::SortingAlgorithm::DebugColor(c) => s += &format!(" {:?}", c),
+            span_sorter::SortingAlgorithm::Stablesort(ties) => s += &format!(" {:?}", ties),
+            _ => {}
+        }
         s += "(by ";
         s += match self.sorter.criteria {
             SortingCriteria::Hue => "Hue",
             SortingCriteria::Brightness => "Bright",
             SortingCriteria::Saturation => "Sat",
         };
+        if let KeySource::Image(_) = self.sorter.key_source {
+            s += " of guide";
+        }
         s += ")";
 
         s
@@ -153,15 +287,79 @@ impl Pixelsorter {
             PathCreator::AllVertically => "vert".into(),
             PathCreator::HorizontalLines => "lr".into(),
             PathCreator::VerticalLines => "ud".into(),
-            PathCreator::Circles => "circ".into(),
-            PathCreator::Spiral => "sprl".into(),
+            PathCreator::Circles(_) => "circ".into(),
+            PathCreator::Spiral(_) => "sprl".into(),
             PathCreator::SquareSpiral => "spSq".into(),
             PathCreator::RectSpiral => "spRe".into(),
             PathCreator::Diagonally(a) => format!("diag{}", a),
-            PathCreator::Hilbert => "hilb".into(),
+            PathCreator::Rotated(a) => format!("rot{}", a),
+            PathCreator::Hilbert { block_size: None, .. } => "hilb".into(),
+            PathCreator::Hilbert { block_size: Some(size), alternate } => {
+                format!("hilb{}{}", size, if alternate { "alt" } else { "" })
+            }
+            PathCreator::Moore => "moore".into(),
+            PathCreator::Peano => "peano".into(),
+            PathCreator::ZOrder => "zord".into(),
+            PathCreator::Serpentine => "serp".into(),
+            PathCreator::Waves { amplitude, wavelength, phase, angle } => {
+                format!("waves{}-{}-{}-{}", amplitude, wavelength, phase, angle)
+            }
+            PathCreator::Polygon { sides, rotation, .. } => format!("poly{}-{}", sides, rotation),
+            PathCreator::Star { points, inner, rotation, .. } => format!("star{}-{}-{}", points, inner, rotation),
+            PathCreator::Curve { function, offset, width, scale } => format!(
+                "curve{}{}-{}-{}",
+                match function {
+                    path_creator::CurveFunction::Atan => "atan",
+                    path_creator::CurveFunction::Sine => "sine",
+                    path_creator::CurveFunction::Parabola => "parab",
+                    path_creator::CurveFunction::Expression(_) => "expr",
+                },
+                offset,
+                width,
+                scale
+            ),
+            PathCreator::Flow(source) => match source {
+                path_creator::FlowSource::Image { smoothing } => format!("flowimg{}", smoothing),
+                path_creator::FlowSource::Noise { scale, seed } => format!("flownoise{}s{}", scale, seed),
+            },
+            PathCreator::Voronoi { size, jitter, order, seed } => format!(
+                "voronoi{}-{}{}s{}",
+                size,
+                jitter,
+                match order {
+                    path_creator::RegionOrder::Rows => "rows",
+                    path_creator::RegionOrder::RandomAngle => "angle",
+                    path_creator::RegionOrder::Spiral => "spiral",
+                },
+                seed
+            ),
+            PathCreator::Segments { size, compactness, order } => format!(
+                "seg{}-{}{}",
+                size,
+                compactness,
+                match order {
+                    path_creator::RegionOrder::Rows => "rows",
+                    path_creator::RegionOrder::RandomAngle => "angle",
+                    path_creator::RegionOrder::Spiral => "spiral",
+                }
+            ),
+            PathCreator::Strokes { width } => format!("strokes{}", width),
+            PathCreator::RandomWalk { angle, bias, seed } => format!("walk{}-{}s{}", angle, bias, seed),
+            PathCreator::Tiles { size, directions, order } => format!(
+                "tiles{}-{}{}",
+                size,
+                directions.to_string().replace("/", ""),
+                match order {
+                    path_creator::TileOrder::Cycle => String::new(),
+                    path_creator::TileOrder::Random { seed } => format!("s{}", seed),
+                }
+            ),
             p => format!("{}", p).to_lowercase(),
         }
         .as_str();
+        if self.polar.is_some() {
+            s += "-polar";
+        }
         s += "-";
         if self.reverse {
             s += "R-"
@@ -169,7 +367,18 @@ impl Pixelsorter {
         s += match self.selector {
             PixelSelector::Full => "full".into(),
             PixelSelector::Fixed { len } => format!("fixed{}", len),
-            PixelSelector::Random { max } => format!("rand{}", max),
+            PixelSelector::Random { min, max, distribution, variation } => format!(
+                "rand{}-{}{}{}",
+                min,
+                max,
+                match distribution {
+                    pixel_selector::RandomDistribution::Uniform => "",
+                    pixel_selector::RandomDistribution::Normal => "norm",
+                    pixel_selector::RandomDistribution::Exponential => "exp",
+                    pixel_selector::RandomDistribution::Perlin => "perlin",
+                },
+                if variation > 0 { format!("v{}", variation) } else { String::new() }
+            ),
             PixelSelector::Threshold { min, max, criteria } => format!(
                 "{}{}-{}",
                 match criteria {
@@ -180,6 +389,31 @@ impl Pixelsorter {
                 min,
                 max
             ),
+            PixelSelector::Hysteresis { start_min, start_max, min, max, criteria } => format!(
+                "hyst{}{}-{}-{}-{}",
+                match criteria {
+                    pixel_selector::PixelSelectCriteria::Hue => "hue",
+                    pixel_selector::PixelSelectCriteria::Brightness => "bright",
+                    pixel_selector::PixelSelectCriteria::Saturation => "sat",
+                },
+                start_min,
+                start_max,
+                min,
+                max
+            ),
+            PixelSelector::Edges { threshold } => format!("edges{}", threshold),
+            PixelSelector::Noise { kind, seed, scale, octaves, threshold } => format!(
+                "{}{}-{}o{}s{}",
+                match kind {
+                    pixel_selector::NoiseKind::Perlin => "perlin",
+                    pixel_selector::NoiseKind::Simplex => "simplex",
+                    pixel_selector::NoiseKind::Worley => "worley",
+                },
+                threshold,
+                scale,
+                octaves,
+                seed
+            ),
         }
         .as_str();
         s += "-";
@@ -187,14 +421,33 @@ impl Pixelsorter {
             span_sorter::SortingAlgorithm::Mapsort => "map",
             span_sorter::SortingAlgorithm::Shellsort => "shell",
             span_sorter::SortingAlgorithm::Glitchsort => "gl",
-            span_sorter::SortingAlgorithm::DebugColor => "debug",
+            span_sorter::SortingAlgorithm::Stablesort(_) => "stable",
+            span_sorter::SortingAlgorithm::DebugColor(_) => "debug",
+            span_sorter::SortingAlgorithm::Reverse => "rev",
+            span_sorter::SortingAlgorithm::Shuffle => "shuffle",
+            span_sorter::SortingAlgorithm::Smear => "smear",
+            span_sorter::SortingAlgorithm::Average => "avg",
+            span_sorter::SortingAlgorithm::Gradient => "grad",
+            span_sorter::SortingAlgorithm::Rotate(_) => "rot",
         };
+        match self.sorter.algorithm {
+            span_sorter::SortingAlgorithm::Rotate(shift) => s += &format!("{}", shift),
+            span_sorter::SortingAlgorithm::DebugColor(c) => s += &format!("-{}", format!("{:?}", c).to_lowercase()),
+            span_sorter::SortingAlgorithm::Stablesort(ties) => s += &format!("-{}", format!("{:?}", ties).to_lowercase()),
+            _ => {}
+        }
         s += "-";
         s += match self.sorter.criteria {
             SortingCriteria::Hue => "hue",
             SortingCriteria::Brightness => "bright",
             SortingCriteria::Saturation => "sat",
         };
+        if let KeySource::Image(_) = self.sorter.key_source {
+            s += "-guide";
+        }
+        if self.sorter.quantize {
+            s += "-int";
+        }
 
         s
     }
@@ -224,21 +477,54 @@ impl Pixelsorter {
             self.sorter.info_string(),
         );
 
+        // The guide image the keys are read from, it gets transformed the same way as the image
+        let guide = self.sorter.guide_image(w, h);
+        if let Some(options) = self.polar {
+            return self.sort_polar(all_pixels, w, h, options, guide);
+        }
+        if let PathCreator::Rotated(angle) = self.path_creator {
+            return self.sort_rotated(all_pixels, w, h, angle, guide, None);
+        }
+
+        // LOOK AT THE WHOLE IMAGE (if the selector needs it)
+        timestart = Instant::now();
+        let field = self.selector.create_field(&all_pixels, w, h);
+        info!("TIME [Selector field]:\t{:?}", timestart.elapsed());
+
         // CUT IMAGE INTO PATHS
         timestart = Instant::now();
         info!("TIME | [Loading pixels]: \t+ {:?}", timestart.elapsed());
-        let ranges = self.path_creator.create_paths(all_pixels, w, h, self.reverse);
+        let ranges = self.create_ranges(all_pixels, w, h);
 
         info!("TIME [Creating Paths]:\t{:?}", timestart.elapsed());
-        timestart = Instant::now();
+        self.sort_ranges(ranges, field.as_deref(), guide.as_ref());
+    }
+
+    /// Cuts the image into paths. The strokes are not part of the path creator, so they are handled here
+    fn create_ranges<'a>(&self, all_pixels: Vec<&'a mut Rgb<u8>>, w: u64, h: u64) -> Vec<PixelSpan<'a>> {
+        if let PathCreator::Strokes { width } = self.path_creator {
+            let mut paths = path_creator::path_strokes(&self.strokes, w, h, width);
+            if self.reverse {
+                paths.iter_mut().for_each(|p| p.reverse());
+            }
+            path_creator::pick_pixels(all_pixels, paths)
+        } else {
+            self.path_creator.create_paths(all_pixels, w, h, self.reverse)
+        }
+    }
+
+    /// Creates the spans on every path and sorts them.
+    /// The keys are read from the guide image at the index of every pixel, if there is one
+    fn sort_ranges(&self, ranges: Vec<PixelSpan<'_>>, field: Option<&[u16]>, guide: Option<&RgbImage>) {
+        let mut timestart = Instant::now();
 
         // CREATE SPANS ON EVERY PATH
-        let mut spans: Vec<Vec<&mut Rgb<u8>>> = Vec::new();
+        let mut spans: Vec<(usize, PixelSpan<'_>)> = Vec::new();
         spans.par_extend(
             ranges
                 .into_par_iter()
-                .map(|r| self.selector.create_spans(&mut r.into()))
-                .flatten(),
+                // Remember the position of every span on its path
+                .flat_map_iter(|r| self.selector.create_spans(&mut r.into(), field).into_iter().enumerate()),
         );
 
         info!("TIME [Selector]:\t{:?}", timestart.elapsed());
@@ -247,20 +533,94 @@ impl Pixelsorter {
         timestart = Instant::now();
 
         // SORT EVERY SPAN
-        spans.into_par_iter().for_each(|mut span| {
-            self.sorter.sort(&mut span);
+        let key_function = self.sorter.key_function();
+        spans.into_par_iter().for_each(|(index, span)| {
+            let info = SpanInfo { start: span.first().map_or(0, |(i, _)| *i), index };
+            let keys: Vec<f32> = span
+                .iter()
+                .map(|(i, px)| match guide {
+                    Some(g) => key_function(g.get_pixel(*i as u32 % g.width(), *i as u32 / g.width())),
+                    None => key_function(px),
+                })
+                .collect();
+            let mut pixels: Vec<&mut Rgb<u8>> = span.into_iter().map(|(_, px)| px).collect();
+            self.sorter.sort_by_keys(&mut pixels, &keys, info);
         });
 
         let timeend = timestart.elapsed();
         info!("TIME [Sorting]: \t{:?}", timeend);
     }
 
+    /// Sorts the rows of a rotated copy of the image and rotates it back.
+    /// If `inside` is given, only the parts of the rows inside of it are sorted
+    fn sort_rotated(&self, all_pixels: Vec<&mut Rgb<u8>>, w: u64, h: u64, angle: f32, guide: Option<RgbImage>, inside: Option<&[bool]>) {
+        let mut timestart = Instant::now();
+        let rotation = rotate::Rotation::new(w, h, angle);
+        let (rw, rh) = rotation.rotated_size();
+        let (mut rotated, mut rows) = rotation.rotate(&all_pixels);
+        let guide = guide.map(|mut g| rotation.rotate(&g.pixels_mut().collect::<Vec<_>>()).0);
+        info!("TIME [Rotating]:\t{:?}", timestart.elapsed());
+
+        if self.reverse {
+            rows.iter_mut().for_each(|r| r.reverse());
+        }
+        let unsorted = rotated.clone();
+        {
+            let rotated_pixels: Vec<&mut Rgb<u8>> = rotated.pixels_mut().collect();
+            let field = self.selector.create_field(&rotated_pixels, rw, rh);
+            let mut ranges = path_creator::pick_pixels(rotated_pixels, rows);
+            if let Some(inside) = inside {
+                ranges = polar::cut_outside(ranges, &rotation.rotate_mask(inside));
+            }
+            self.sort_ranges(ranges, field.as_deref(), guide.as_ref());
+        }
+
+        timestart = Instant::now();
+        rotation.rotate_back(&rotated, &rotate::changed_pixels(&unsorted, &rotated), all_pixels);
+        info!("TIME [Rotating back]:\t{:?}", timestart.elapsed());
+    }
+
+    /// Sorts the image unwrapped into polar coordinates and wraps it back
+    fn sort_polar(&self, all_pixels: Vec<&mut Rgb<u8>>, w: u64, h: u64, options: path_creator::RoundOptions, guide: Option<RgbImage>) {
+        let mut timestart = Instant::now();
+        let polar = polar::Polar::new(w, h, options);
+        let (pw, ph) = polar.unwrapped_size();
+        let (mut unwrapped, inside) = polar.unwrap(&all_pixels);
+        let guide = guide.map(|mut g| polar.unwrap(&g.pixels_mut().collect::<Vec<_>>()).0);
+        info!("TIME [Unwrapping]:\t{:?}", timestart.elapsed());
+
+        let unsorted = unwrapped.clone();
+        {
+            let unwrapped_pixels: Vec<&mut Rgb<u8>> = unwrapped.pixels_mut().collect();
+            if let PathCreator::Rotated(angle) = self.path_creator {
+                self.sort_rotated(unwrapped_pixels, pw, ph, angle, guide, Some(&inside));
+            } else {
+                let field = self.selector.create_field(&unwrapped_pixels, pw, ph);
+                let ranges = polar::cut_outside(self.create_ranges(unwrapped_pixels, pw, ph), &inside);
+                self.sort_ranges(ranges, field.as_deref(), guide.as_ref());
+            }
+        }
+
+        timestart = Instant::now();
+        polar.wrap_back(&unwrapped, &rotate::changed_pixels(&unsorted, &unwrapped), all_pixels);
+        info!("TIME [Wrapping back]:\t{:?}", timestart.elapsed());
+    }
+
+    /// Checks how well the paths cover the image, like [PathCreator::coverage], but including the strokes
+    pub fn coverage(&self, img: &RgbImage) -> path_creator::Coverage {
+        match self.path_creator {
+            PathCreator::Strokes { width } => {
+                let (w, h) = (img.width() as u64, img.height() as u64);
+                path_creator::Coverage::of(&path_creator::path_strokes(&self.strokes, w, h, width), w, h)
+            }
+            // Only strokes have no coverage on their own
+            _ => self.path_creator.coverage(img).unwrap_or_default(),
+        }
+    }
+
     pub fn mask(&self, img: &mut RgbImage) -> bool {
+        let (w, h) = (img.width().into(), img.height().into());
         let mut all_pixels: Vec<&mut Rgb<u8>> = img.pixels_mut().collect();
-        if let PixelSelector::Threshold { min, max, criteria } = self.selector {
-            self.selector.mask(&mut all_pixels);
-            return true;
-        }
-        return false;
+        self.selector.mask(&mut all_pixels, w, h).is_ok()
     }
 }
@@ -1,13 +1,16 @@
 use image::RgbImage;
 use log::{error, info, warn};
 use pixelsortery::{
-    path_creator::PathCreator,
+    analysis::{self, ThresholdSuggestion},
+    path_creator::{
+        CurveFunction, FlowSource, Formula, PathCreator, RegionOrder, RoundOptions, TileDirection, TileDirections, TileOrder,
+    },
     pixel_selector::{
-        PixelSelectCriteria, PixelSelector
+        NoiseKind, PixelSelectCriteria, PixelSelector, RandomDistribution
     },
-    span_sorter::{SortingAlgorithm, SortingCriteria},
+    span_sorter::{DebugColoring, KeySource, SortingAlgorithm, SortingCriteria, TiePolicy},
 };
-use std::{io::Read, path::PathBuf, str::FromStr, time::Duration};
+use std::{io::Read, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
 use std::time::Instant;
 use std::{collections::VecDeque, env, process::exit};
 
@@ -28,35 +31,378 @@ fn parse_parameter<T: FromStr>(arg: Option<String>, usage: &str) -> T {
     exit(-1);
 }
 
-fn parse_thres_selector_parameters(arg: Option<String>) -> PixelSelector {
-    // parse the string after that: --thres hue:10:200
+/// Parses the criteria of a threshold-like selector and returns it with its default min and max value
+fn parse_select_criteria(arg: &str, usage: &str) -> (PixelSelectCriteria, u64, u64) {
+    match arg {
+        "hue" => (PixelSelectCriteria::Hue, 0, 360),
+        "bright" => (PixelSelectCriteria::Brightness, 0, 255),
+        "sat" => (PixelSelectCriteria::Saturation, 0, 255),
+        _ => {
+            eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
+            exit(-1)
+        }
+    }
+}
+
+fn parse_thres_selector_parameters(arg: Option<String>) -> (PixelSelector, Option<ThresholdSuggestion>) {
+    // parse the string after that: --thres hue:10:200, --thres bright:auto, --thres bright:p20:p80 or --thres bright:top10
+    let usage = "--thres <hue|bright|sat>:<0:255|auto|p20:p80|top10>";
     if let Some(arg2) = arg {
         let mut thres_opts: VecDeque<&str> = VecDeque::from_iter(arg2.split(":"));
-        let (criteria, defaultmin, defaultmax) = match thres_opts.pop_front().unwrap_or("") {
-            "hue" => (PixelSelectCriteria::Hue, 0, 360),
-            "bright" => (PixelSelectCriteria::Brightness, 0, 255),
-            "sat" => (PixelSelectCriteria::Saturation, 0, 255),
+        let (criteria, defaultmin, defaultmax) = parse_select_criteria(thres_opts.pop_front().unwrap_or(""), usage);
+        let selector = |min, max| PixelSelector::Threshold { min, max, criteria };
+
+        // The suggested thresholds can only be calculated once the image is loaded
+        let percentile = |s: &str| s.strip_prefix("p").and_then(|p| p.parse::<f32>().ok());
+        match (thres_opts.pop_front().unwrap_or(""), thres_opts.pop_front().unwrap_or("")) {
+            ("auto", _) => return (selector(defaultmin, defaultmax), Some(ThresholdSuggestion::Otsu)),
+            (top, _) if top.starts_with("top") => {
+                if let Ok(percent) = top[3..].parse() {
+                    return (selector(defaultmin, defaultmax), Some(ThresholdSuggestion::Top(percent)));
+                }
+            }
+            (low, high) if low.starts_with("p") => {
+                if let (Some(low), Some(high)) = (percentile(low), percentile(high)) {
+                    return (selector(defaultmin, defaultmax), Some(ThresholdSuggestion::Percentile(low, high)));
+                }
+            }
+            (min, max) => {
+                let min = min.parse().unwrap_or(defaultmin);
+                let max = max.parse().unwrap_or(defaultmax);
+                return (selector(min, max), None);
+            }
+        }
+    }
+    eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
+    exit(-1)
+}
+
+fn parse_hysteresis_selector_parameters(arg: Option<String>) -> PixelSelector {
+    // parse the string after that: --hysteresis bright:100:200:60:255
+    let usage = "--hysteresis <hue|bright|sat>:<start_min>:<start_max>:<min>:<max>";
+    if let Some(arg2) = arg {
+        let mut opts: VecDeque<&str> = VecDeque::from_iter(arg2.split(":"));
+        let (criteria, defaultmin, defaultmax) = parse_select_criteria(opts.pop_front().unwrap_or(""), usage);
+        let mut next_value = |default: u64| opts.pop_front().unwrap_or("").parse().unwrap_or(default);
+        let start_min = next_value(defaultmin);
+        let start_max = next_value(defaultmax);
+        // The continue range defaults to the start range
+        let min = next_value(start_min);
+        let max = next_value(start_max);
+        return PixelSelector::Hysteresis { start_min, start_max, min, max, criteria };
+    } else {
+        eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
+        exit(-1)
+    }
+}
+
+fn parse_random_selector_parameters(arg: Option<String>) -> PixelSelector {
+    // parse the string after that: --random 30 or --random 5:30:normal:20
+    let usage = "--random [<min>:]<max>[:<uniform|normal|exp|perlin>[:<variation>]]";
+    if let Some(arg2) = arg {
+        let mut opts: VecDeque<&str> = VecDeque::from_iter(arg2.split(":"));
+        // A single value is just the max length
+        let (min, max) = match (opts.pop_front(), opts.pop_front()) {
+            (Some(max), None) => (Some("1"), Some(max)),
+            (min, max) => (min, max),
+        };
+        let (Some(Ok(min)), Some(Ok(max))) = (min.map(str::parse), max.map(str::parse)) else {
+            eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
+            exit(-1)
+        };
+        let distribution = match opts.pop_front().unwrap_or("uniform") {
+            "uniform" => RandomDistribution::Uniform,
+            "normal" => RandomDistribution::Normal,
+            "exp" => RandomDistribution::Exponential,
+            "perlin" => RandomDistribution::Perlin,
             _ => {
-                eprintln!("[ERROR] Wrong syntax, usage: --thres <hue|bright|sat>:0:255");
+                eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
                 exit(-1)
             }
         };
-        let min = thres_opts
-            .pop_front()
-            .unwrap_or("")
-            .parse()
-            .unwrap_or(defaultmin);
-        let max = thres_opts
-            .pop_front()
-            .unwrap_or("")
-            .parse()
-            .unwrap_or(defaultmax);
-        return PixelSelector::Threshold { min, max, criteria,
+        let variation = opts.pop_front().unwrap_or("").parse().unwrap_or(0);
+        return PixelSelector::Random { min, max, distribution, variation };
+    } else {
+        eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
+        exit(-1)
+    }
+}
+
+fn parse_noise_selector_parameters(arg: Option<String>) -> PixelSelector {
+    // parse the string after that: --noise perlin:128:64:3:42
+    let usage = "--noise <perlin|simplex|worley>:<threshold>[:<scale>[:<octaves>[:<seed>]]]";
+    if let Some(arg2) = arg {
+        let mut opts: VecDeque<&str> = VecDeque::from_iter(arg2.split(":"));
+        let kind = match opts.pop_front().unwrap_or("") {
+            "perlin" => NoiseKind::Perlin,
+            "simplex" => NoiseKind::Simplex,
+            "worley" => NoiseKind::Worley,
+            _ => {
+                eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
+                exit(-1)
+            }
         };
+        let threshold = opts.pop_front().unwrap_or("").parse().unwrap_or(128);
+        let scale = opts.pop_front().unwrap_or("").parse().unwrap_or(64);
+        let octaves = opts.pop_front().unwrap_or("").parse().unwrap_or(1);
+        let seed = opts.pop_front().unwrap_or("").parse().unwrap_or(0);
+        return PixelSelector::Noise { kind, seed, scale, octaves, threshold };
     } else {
-        eprintln!("[ERROR] Wrong syntax, usage: --thres <hue|bright|sat>:0:255");
+        eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
+        exit(-1)
+    }
+}
+
+fn parse_center_parameter(arg: Option<String>) -> (f32, f32) {
+    // parse the string after that: --center 0.3,0.7
+    if let Some((x, y)) = arg.as_deref().and_then(|s| s.split_once(",")) {
+        if let (Ok(x), Ok(y)) = (x.parse(), y.parse()) {
+            return (x, y);
+        }
+    }
+    eprintln!("[ERROR] Wrong syntax, usage: --center <x>,<y>");
+    exit(-1);
+}
+
+fn parse_hilbert_tiles_parameters(arg: Option<String>) -> PathCreator {
+    // parse the string after that: --hilbert-tiles 64:alt
+    let usage = "--hilbert-tiles <size>[:alt]";
+    if let Some(arg2) = arg {
+        let (size, alternate) = match arg2.split_once(":") {
+            Some((size, "alt")) => (size, true),
+            Some(_) => ("", false),
+            None => (arg2.as_str(), false),
+        };
+        if let Ok(size) = size.parse::<u32>() {
+            return PathCreator::Hilbert { block_size: Some(size), alternate };
+        }
+    }
+    eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
+    exit(-1)
+}
+
+/// Parses the optional offset, width and scale of a curve, using the defaults for missing ones
+fn parse_curve_values(opts: &[&str], defaults: (i32, f32, f32)) -> Option<(i32, f32, f32)> {
+    if opts.len() > 3 {
+        return None;
+    }
+    let offset = opts.get(0).map_or(Some(defaults.0), |s| s.parse().ok())?;
+    let width = opts.get(1).map_or(Some(defaults.1), |s| s.parse().ok())?;
+    let scale = opts.get(2).map_or(Some(defaults.2), |s| s.parse().ok())?;
+    Some((offset, width, scale))
+}
+
+fn parse_curve_parameters(arg: Option<String>) -> PathCreator {
+    // parse the string after that: --curve sine:0:200:1 or --curve "expr=sin(t*pi)*t:0:200"
+    let usage = "--curve <atan|sine|parabola|expr=<formula>>[:<offset>[:<width>[:<scale>]]]";
+    if let Some(arg2) = arg {
+        let opts: Vec<&str> = arg2.split(":").collect();
+        let function = match opts[0] {
+            "atan" => Some(CurveFunction::Atan),
+            "sine" => Some(CurveFunction::Sine),
+            "parabola" => Some(CurveFunction::Parabola),
+            f => f.strip_prefix("expr=").and_then(|formula| {
+                let formula = Formula::new(formula)?;
+                // Check the syntax once, so we don't fail silently later
+                if let Err(e) = formula.eval(0.0) {
+                    eprintln!("[ERROR] Invalid formula: {}", e);
+                    exit(-1);
+                }
+                Some(CurveFunction::Expression(formula))
+            }),
+        };
+        if let (Some(function), Some((offset, width, scale))) = (function, parse_curve_values(&opts[1..], (0, 200.0, 1.0))) {
+            return PathCreator::Curve { function, offset, width, scale };
+        }
+    }
+    eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
+    exit(-1)
+}
+
+fn parse_region_order(s: &str) -> Option<RegionOrder> {
+    match s {
+        "rows" => Some(RegionOrder::Rows),
+        "angle" => Some(RegionOrder::RandomAngle),
+        "spiral" => Some(RegionOrder::Spiral),
+        _ => None,
+    }
+}
+
+fn parse_voronoi_parameters(arg: Option<String>) -> PathCreator {
+    // parse the string after that: --voronoi 100:1:angle:42
+    let usage = "--voronoi <size>[:<jitter>[:<rows|angle|spiral>[:<seed>]]]";
+    if let Some(arg2) = arg {
+        let opts: Vec<&str> = arg2.split(":").collect();
+        let size = opts.first().and_then(|s| s.parse::<u32>().ok());
+        let jitter = opts.get(1).map_or(Some(1.0), |s| s.parse::<f32>().ok());
+        let order = opts.get(2).map_or(Some(RegionOrder::Rows), |s| parse_region_order(s));
+        let seed = opts.get(3).map_or(Some(0), |s| s.parse::<u64>().ok());
+        if let (Some(size), Some(jitter), Some(order), Some(seed), true) = (size, jitter, order, seed, opts.len() <= 4) {
+            return PathCreator::Voronoi { size, jitter, order, seed };
+        }
+    }
+    eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
+    exit(-1)
+}
+
+fn parse_key_image(arg: Option<String>) -> KeySource {
+    let Some(path) = arg else {
+        eprintln!("[ERROR] Wrong syntax, usage: --key-image <path>");
         exit(-1)
+    };
+    match image::open(&path) {
+        Ok(img) => KeySource::Image(Arc::new(img.into_rgb8())),
+        Err(e) => {
+            eprintln!("[ERROR] Could not open the key image '{}': {}", path, e);
+            exit(-1)
+        }
+    }
+}
+
+fn parse_stablesort_parameters(arg: Option<String>) -> SortingAlgorithm {
+    let usage = "--stablesort <keep|reverse|random>";
+    let ties = match arg.as_deref() {
+        Some("keep") => TiePolicy::Keep,
+        Some("reverse") => TiePolicy::Reverse,
+        Some("random") => TiePolicy::Random,
+        _ => {
+            eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
+            exit(-1)
+        }
+    };
+    SortingAlgorithm::Stablesort(ties)
+}
+
+fn parse_walk_parameters(arg: Option<String>) -> PathCreator {
+    // parse the string after that: --walk 90:2:42
+    let usage = "--walk <angle>[:<bias>[:<seed>]]";
+    if let Some(arg2) = arg {
+        let opts: Vec<&str> = arg2.split(":").collect();
+        let angle = opts.first().and_then(|s| s.parse::<f32>().ok()).filter(|a| a.is_finite());
+        let bias = opts.get(1).map_or(Some(1.0), |s| s.parse::<f32>().ok()).filter(|b| b.is_finite());
+        let seed = opts.get(2).map_or(Some(0), |s| s.parse::<u64>().ok());
+        if let (Some(angle), Some(bias), Some(seed), true) = (angle, bias, seed, opts.len() <= 3) {
+            return PathCreator::RandomWalk { angle, bias, seed };
+        }
+    }
+    eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
+    exit(-1)
+}
+
+fn parse_tiles_parameters(arg: Option<String>) -> PathCreator {
+    // parse the string after that: --tiles 100:up,right:42
+    let usage = "--tiles <size>:<right|left|down|up>[,...][:<seed>]";
+    if let Some(arg2) = arg {
+        let opts: Vec<&str> = arg2.split(":").collect();
+        let size = opts.first().and_then(|s| s.parse::<u32>().ok());
+        let directions = opts.get(1).and_then(|s| {
+            let list: Option<Vec<TileDirection>> = s
+                .split(",")
+                .map(|d| match d {
+                    "right" => Some(TileDirection::Right),
+                    "left" => Some(TileDirection::Left),
+                    "down" => Some(TileDirection::Down),
+                    "up" => Some(TileDirection::Up),
+                    _ => None,
+                })
+                .collect();
+            TileDirections::new(&list?)
+        });
+        // Without a seed, the directions are cycled
+        let order = opts.get(2).map_or(Some(TileOrder::Cycle), |s| s.parse::<u64>().ok().map(|seed| TileOrder::Random { seed }));
+        if let (Some(size), Some(directions), Some(order), true) = (size, directions, order, opts.len() <= 3) {
+            return PathCreator::Tiles { size, directions, order };
+        }
     }
+    eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
+    exit(-1)
+}
+
+fn parse_segments_parameters(arg: Option<String>) -> PathCreator {
+    // parse the string after that: --segments 40:10:spiral
+    let usage = "--segments <size>[:<compactness>[:<rows|angle|spiral>]]";
+    if let Some(arg2) = arg {
+        let opts: Vec<&str> = arg2.split(":").collect();
+        let size = opts.first().and_then(|s| s.parse::<u32>().ok());
+        let compactness = opts.get(1).map_or(Some(10.0), |s| s.parse::<f32>().ok());
+        let order = opts.get(2).map_or(Some(RegionOrder::Rows), |s| parse_region_order(s));
+        if let (Some(size), Some(compactness), Some(order), true) = (size, compactness, order, opts.len() <= 3) {
+            return PathCreator::Segments { size, compactness, order };
+        }
+    }
+    eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
+    exit(-1)
+}
+
+fn parse_waves_parameters(arg: Option<String>) -> PathCreator {
+    // parse the string after that: --waves 20:200:0:45
+    let usage = "--waves <amplitude>:<wavelength>[:<phase>[:<angle>]]";
+    if let Some(arg2) = arg {
+        let mut opts: VecDeque<&str> = VecDeque::from_iter(arg2.split(":"));
+        let mut next_value = || opts.pop_front().and_then(|s| s.parse::<f32>().ok());
+        if let (Some(amplitude), Some(wavelength)) = (next_value(), next_value()) {
+            let phase = next_value().unwrap_or(0.0);
+            let angle = next_value().unwrap_or(0.0);
+            return PathCreator::Waves { amplitude, wavelength, phase, angle };
+        }
+    }
+    eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
+    exit(-1)
+}
+
+fn parse_star_parameters(arg: Option<String>) -> PathCreator {
+    // parse the string after that: --star 5:0.5:0
+    let usage = "--star <points>[:<inner>[:<rotation>]]";
+    if let Some(arg2) = arg {
+        let mut opts: VecDeque<&str> = VecDeque::from_iter(arg2.split(":"));
+        if let Some(Ok(points)) = opts.pop_front().map(|s| s.parse::<u32>()) {
+            let mut next_value = || opts.pop_front().and_then(|s| s.parse::<f32>().ok());
+            let inner = next_value().unwrap_or(0.5);
+            let rotation = next_value().unwrap_or(0.0);
+            return PathCreator::Star { points, inner, rotation, options: RoundOptions::default() };
+        }
+    }
+    eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
+    exit(-1)
+}
+
+fn parse_polygon_parameters(arg: Option<String>) -> PathCreator {
+    // parse the string after that: --polygon 6:30
+    let usage = "--polygon <sides>[:<rotation>]";
+    if let Some(arg2) = arg {
+        let mut opts: VecDeque<&str> = VecDeque::from_iter(arg2.split(":"));
+        if let Some(Ok(sides)) = opts.pop_front().map(|s| s.parse::<u32>()) {
+            let rotation = opts.pop_front().and_then(|s| s.parse::<f32>().ok()).unwrap_or(0.0);
+            return PathCreator::Polygon { sides, rotation, options: RoundOptions::default() };
+        }
+    }
+    eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
+    exit(-1)
+}
+
+fn parse_flow_parameters(arg: Option<String>) -> PathCreator {
+    // parse the string after that: --flow image:4 or --flow noise:128:42
+    let usage = "--flow image[:<smoothing>] | noise[:<scale>[:<seed>]]";
+    if let Some(arg2) = arg {
+        let mut opts: VecDeque<&str> = VecDeque::from_iter(arg2.split(":"));
+        let source = opts.pop_front();
+        if let Ok(values) = opts.iter().map(|s| s.parse::<u64>()).collect::<Result<Vec<_>, _>>() {
+            match (source, values.as_slice()) {
+                (Some("image"), [] | [_]) => {
+                    let smoothing = values.first().copied().unwrap_or(4) as u32;
+                    return PathCreator::Flow(FlowSource::Image { smoothing });
+                }
+                (Some("noise"), [] | [_] | [_, _]) => {
+                    let scale = values.first().copied().unwrap_or(128) as u32;
+                    let seed = values.get(1).copied().unwrap_or(0);
+                    return PathCreator::Flow(FlowSource::Noise { scale, seed });
+                }
+                _ => {}
+            }
+        }
+    }
+    eprintln!("[ERROR] Wrong syntax, usage: {}", usage);
+    exit(-1)
 }
 
 const HELP_STRING: &str = "
@@ -77,7 +423,8 @@ const HELP_STRING: &str = "
    -v | --version : Show version and exit
    --quiet        : Make the program shut up
 
-   --show-mask    : Outputs a mask showing what areas would be sorted (requires --thres)
+   --coverage     : Print how many pixels the direction misses or visits more than once
+   --show-mask    : Outputs a mask showing what areas would be sorted (requires --thres, --hysteresis, --edges or --noise)
    --gui          : Starts the gui;
                     | When using the gui, setting <output> is optional
    [VIDEO]
@@ -96,29 +443,107 @@ const HELP_STRING: &str = "
    --spiral-square    : Sort in a squared spiral
    --spiral-rect      : Sort in a rectangular spiral
    --diagonal <angle> : Sort lines tilted by an angle
+   --rotated <angle>  : Sort smooth lines at an angle (0 is to the right, 90 down) by rotating the image
    --hilbert          : Sort along the hilbert curve pattern
+   --hilbert-tiles <size>[:alt]
+                      : Sort along hilbert curves in blocks of <size> pixels, with alt every other block goes the other way
+   --moore            : Sort along the moore curve pattern (a closed hilbert curve)
+   --peano            : Sort along the peano curve pattern
+   --z-order          : Sort along the z-order (morton) curve pattern
+   --serpentine       : Sort all rows, alternating between left to right and right to left
    --rays             : Sort from the center in all directions
+     --center <x>,<y>     : Set the center of circles, spirals, rays, polygons and stars, relative to the image size (default 0.5,0.5)
+     --ellipse <ratio>    : Set the ratio between the width and height of circles, spirals, polygons and stars (default 1.0)
+     --start-angle <angle>: Set the angle where circles and spirals start (default -90, the top)
+   --polar            : Unwrap the image into polar coordinates around the center before sorting and wrap it back after,
+                        so rows become circles and columns become rays. Works with every direction and uses the options above
    --reverse          : Sort in the opposite direction
-   --x3               : Sort in a curve
+   --x3 [<offset>:<width>:<slope>]
+                      : Sort in a curve, <offset> moves it up/down, <width> is the size in pixels (default 0:200:10)
+   --curve <atan|sine|parabola|expr=<formula>>[:<offset>[:<width>[:<scale>]]]
+                      : Sort in columns, moving every row to the side by <width> (default 200) pixels times a function.
+                        A formula uses t (0 at the center row, -<scale> to <scale> from top to bottom, default 1)
+                        f.e. expr=sin(t*pi)*t^2 (supports + - * / ^, pi, e, sin, cos, tan, sqrt, abs, exp, ln, ...)
+   --waves <amplitude>:<wavelength>[:<phase>[:<angle>]]
+                      : Sort along sine waves, tilted by <angle> (phase and angle in degrees)
+   --polygon <sides>[:<rotation>]
+                      : Sort in concentric polygons (at least 3 sides), rotated by <rotation> degrees.
+                        Uses --center and --ellipse like the round paths
+   --star <points>[:<inner>[:<rotation>]]
+                      : Sort in concentric stars (at least 3 points), <inner> is the ratio of the inner to the outer radius (default 0.5).
+                        Uses --center and --ellipse like the round paths
+   --voronoi <size>[:<jitter>[:<rows|angle|spiral>[:<seed>]]]
+                      : Sort inside voronoi cells of about <size> pixels. <jitter> (0-1, default 1) moves their centers off the grid.
+                        The pixels of every cell are sorted in rows, in lines at a random angle or in a spiral
+   --segments <size>[:<compactness>[:<rows|angle|spiral>]]
+                      : Sort inside superpixels of about <size> pixels, which follow the colors of the image.
+                        A higher <compactness> (default 10) makes them rounder
+   --tiles <size>:<right|left|down|up>[,...][:<seed>]
+                      : Cut the image into tiles of <size> pixels, each sorted in a direction from the list (up to 8).
+                        The directions are cycled (two make a checkerboard), or picked randomly if a <seed> is given
+   --walk <angle>[:<bias>[:<seed>]]
+                      : Sort along self-avoiding random walks, which prefer steps towards <angle> degrees.
+                        A higher <bias> (default 1, at most 50) makes them straighter, 0 lets them wander in any direction
+   --flow image[:<smoothing>]
+                      : Sort along the edges of the image, smoothed over <smoothing> pixels (default 4)
+   --flow noise[:<scale>[:<seed>]]
+                      : Sort along streamlines of a noise field with cells of <scale> pixels (default 128)
 
 ============= Span-Selection Options ============
   [Choose which pixels are valid to form a span]
 
-   --random <max>                        : Sort spans of random length between 0 and <max>
+   --random [<min>:]<max>[:<dist>[:<variation>]]
+                                         : Sort spans of random length between <min> (default 1) and <max>
+                                           <dist> is one of uniform (default), normal, exp or perlin
+                                           <variation> scales the lengths of each path randomly by up to that many percent
    --fixed  <max>                        : Sort spans of a fixed length <max>
    --thres <hue|bright|sat>:<min>:<max>  : Mark pixels as valid if [hue|bright|sat] is between <min> and <max>
+           <hue|bright|sat>:auto         : Choose the range automatically (Otsu's method)
+           <hue|bright|sat>:p<low>:p<high>
+                                         : Choose the range between two percentiles of the image, f.e. bright:p20:p80
+           <hue|bright|sat>:top<percent> : Choose the range containing the top <percent>% of pixels, f.e. bright:top10
+   --hysteresis <hue|bright|sat>:<start_min>:<start_max>:<min>:<max>
+                                         : Start spans on pixels between <start_min> and <start_max>
+                                           and continue them while pixels are between <min> and <max>
+   --edges  <threshold>                  : End spans at edges stronger than <threshold> (0-255)
+   --noise <perlin|simplex|worley>:<threshold>[:<scale>[:<octaves>[:<seed>]]]
+                                         : Mark pixels as valid if a noise field at their position is above <threshold> (0-255)
+                                           <scale> is the size of a noise cell in pixels (default 64)
 
 ============ Sorting Algorithm Options ==========
 
-   --mapsort    : Default. O(n)
+   --mapsort    : Default. O(n) with --integer-keys, otherwise pixels with the same integer key are sorted by the exact key
    --shellsort  : Also cool.
    --glitchsort : Used to create a glitch-like effect
+   --stablesort <keep|reverse|random>
+                : Stable sort, pixels with equal keys keep their order, get reversed
+                  or get a random order that stays the same between runs
+
+   Span effects, instead of sorting:
+   --reverse-spans         : Reverse the order of the pixels in every span
+   --shuffle               : Put the pixels of every span in a random order, which stays the same between runs
+   --smear                 : Fill every span with its first pixel
+   --average               : Fill every span with its average color
+   --gradient              : Fill every span with a gradient between its first and last pixel
+   --rotate-spans <shift>  : Shift the pixels of every span cyclically by <shift> pixels, negative values shift backwards
+
+   Debugging, paint every span in one color:
+   --debugcolors           : A random color per span, which stays the same between runs
+   --debugcolors-length    : From blue for short spans to red for long spans
+   --debugcolors-index     : A random color per position of the span on its path
 
 ================ Sorting Options ================
 
    --hue        : Sort Pixels by Hue
    --saturation : Sort Pixels by Saturation
    --brightness : Sort Pixels by Brightness
+   --key-image <path>
+                : Sort Pixels by the hue/saturation/brightness of a guide image (f.e. a depth map) at the same position.
+                  It gets stretched to the size of the sorted image
+   --integer-keys
+                : Round hue, saturation and brightness down to integers before sorting.
+                  Pixels with almost the same color then get the same key, like in older versions.
+                  Thresholds always include everything that rounds down to their bounds
 
 ==================== Notes ======================
    Options marked with [VIDEO] only work on builds with video support.
@@ -156,7 +581,11 @@ fn main() {
     let mut ps = pixelsortery::Pixelsorter::new();
     let mut do_reverse = false;
     let mut show_mask = false;
+    let mut show_coverage = false;
+    let mut polar = false;
     let mut start_gui = false;
+    let mut threshold_suggestion = None;
+    let mut round_options = RoundOptions::default();
 
     #[cfg(feature = "video")]
     let mut frame_ts = None;
@@ -177,13 +606,17 @@ fn main() {
 
             "--gui" => start_gui = true,
             "--show-mask" => show_mask = true,
+            "--coverage"  => show_coverage = true,
 
             #[cfg(feature = "video")]
             "--at" => frame_ts = Some(parse_parameter::<f32>(args.pop_front(), "--at <frame_timestamp> Timestamp is the frame number in the time base of the stream")),
 
-            "--random" => ps.selector = PixelSelector::Random { max: parse_parameter(args.pop_front(), "--random <max>")},
+            "--random" => ps.selector = parse_random_selector_parameters(args.pop_front()),
             "--fixed"  => ps.selector = PixelSelector::Fixed  { len: parse_parameter(args.pop_front(), "--fixed <len>")},
-            "--thres"  => ps.selector = parse_thres_selector_parameters(args.pop_front()),
+            "--thres"  => (ps.selector, threshold_suggestion) = parse_thres_selector_parameters(args.pop_front()),
+            "--hysteresis" => ps.selector = parse_hysteresis_selector_parameters(args.pop_front()),
+            "--noise"  => ps.selector = parse_noise_selector_parameters(args.pop_front()),
+            "--edges"  => ps.selector = PixelSelector::Edges { threshold: parse_parameter(args.pop_front(), "--edges <threshold>")},
 
             "--vertical"   => ps.path_creator = PathCreator::AllVertically,
             "--horizontal" => ps.path_creator = PathCreator::AllHorizontally,
@@ -191,24 +624,64 @@ fn main() {
             "--left"       => { ps.path_creator = PathCreator::HorizontalLines; ps.reverse = true},
             "--down"       =>   ps.path_creator = PathCreator::VerticalLines,
             "--up"         => { ps.path_creator = PathCreator::VerticalLines;   ps.reverse = true},
-            "--rays"           =>   ps.path_creator = PathCreator::Rays,
-            "--circles"           =>   ps.path_creator = PathCreator::Circles,
-            "--spiral"            =>   ps.path_creator = PathCreator::Spiral,
+            "--rays"           =>   ps.path_creator = PathCreator::Rays(RoundOptions::default()),
+            "--circles"           =>   ps.path_creator = PathCreator::Circles(RoundOptions::default()),
+            "--spiral"            =>   ps.path_creator = PathCreator::Spiral(RoundOptions::default()),
+            "--center"     => round_options.center = parse_center_parameter(args.pop_front()),
+            "--ellipse"    => round_options.ellipse = parse_parameter(args.pop_front(), "--ellipse <ratio>"),
+            "--polar"      => polar = true,
+            "--start-angle" => round_options.start_angle = parse_parameter(args.pop_front(), "--start-angle <angle>"),
             "--spiral-square"     =>   ps.path_creator = PathCreator::SquareSpiral,
             "--spiral-rect"       =>   ps.path_creator = PathCreator::RectSpiral,
             "--diagonal"   => ps.path_creator = PathCreator::Diagonally(parse_parameter(args.pop_front(), "--diagonal <angle>")),
-            "--hilbert"    => ps.path_creator = PathCreator::Hilbert,
-            "--x3"         => ps.path_creator = PathCreator::X3(0, 200, 10.0),
+            "--rotated"    => ps.path_creator = PathCreator::Rotated(parse_parameter(args.pop_front(), "--rotated <angle>")),
+            "--hilbert"    => ps.path_creator = PathCreator::Hilbert { block_size: None, alternate: false },
+            "--hilbert-tiles" => ps.path_creator = parse_hilbert_tiles_parameters(args.pop_front()),
+            "--moore"      => ps.path_creator = PathCreator::Moore,
+            "--peano"      => ps.path_creator = PathCreator::Peano,
+            "--z-order"    => ps.path_creator = PathCreator::ZOrder,
+            "--serpentine" => ps.path_creator = PathCreator::Serpentine,
+            "--x3"         => {
+                // The parameters are optional, so only take the next argument if it looks like them
+                let mut values = (0, 200.0, 10.0);
+                let given = args.front().map(|a| a.split(":").collect::<Vec<_>>());
+                if let Some(v) = given.filter(|v| v.len() == 3).and_then(|v| parse_curve_values(&v, values)) {
+                    args.pop_front();
+                    values = v;
+                }
+                let (offset, width, scale) = values;
+                ps.path_creator = PathCreator::Curve { function: CurveFunction::Atan, offset, width, scale };
+            }
+            "--curve"      => ps.path_creator = parse_curve_parameters(args.pop_front()),
+            "--waves"      => ps.path_creator = parse_waves_parameters(args.pop_front()),
+            "--polygon"    => ps.path_creator = parse_polygon_parameters(args.pop_front()),
+            "--star"       => ps.path_creator = parse_star_parameters(args.pop_front()),
+            "--voronoi"    => ps.path_creator = parse_voronoi_parameters(args.pop_front()),
+            "--walk"       => ps.path_creator = parse_walk_parameters(args.pop_front()),
+            "--tiles"      => ps.path_creator = parse_tiles_parameters(args.pop_front()),
+            "--segments"   => ps.path_creator = parse_segments_parameters(args.pop_front()),
+            "--flow"       => ps.path_creator = parse_flow_parameters(args.pop_front()),
             "--reverse"    => do_reverse = true,
 
             "--hue"         => ps.sorter.criteria = SortingCriteria::Hue,
             "--brightness"  => ps.sorter.criteria = SortingCriteria::Brightness,
             "--saturation"  => ps.sorter.criteria = SortingCriteria::Saturation,
+            "--key-image"   => ps.sorter.key_source = parse_key_image(args.pop_front()),
+            "--integer-keys" => ps.sorter.quantize = true,
 
-            "--debugcolors" => ps.sorter.algorithm = SortingAlgorithm::DebugColor,
+            "--debugcolors" => ps.sorter.algorithm = SortingAlgorithm::DebugColor(DebugColoring::Start),
+            "--debugcolors-length" => ps.sorter.algorithm = SortingAlgorithm::DebugColor(DebugColoring::Length),
+            "--debugcolors-index"  => ps.sorter.algorithm = SortingAlgorithm::DebugColor(DebugColoring::Index),
             "--glitchsort"  => ps.sorter.algorithm = SortingAlgorithm::Glitchsort,
             "--shellsort"   => ps.sorter.algorithm = SortingAlgorithm::Shellsort,
             "--mapsort"     => ps.sorter.algorithm = SortingAlgorithm::Mapsort,
+            "--stablesort"  => ps.sorter.algorithm = parse_stablesort_parameters(args.pop_front()),
+            "--reverse-spans" => ps.sorter.algorithm = SortingAlgorithm::Reverse,
+            "--shuffle"     => ps.sorter.algorithm = SortingAlgorithm::Shuffle,
+            "--smear"       => ps.sorter.algorithm = SortingAlgorithm::Smear,
+            "--average"     => ps.sorter.algorithm = SortingAlgorithm::Average,
+            "--gradient"    => ps.sorter.algorithm = SortingAlgorithm::Gradient,
+            "--rotate-spans" => ps.sorter.algorithm = SortingAlgorithm::Rotate(parse_parameter(args.pop_front(), "--rotate-spans <shift>")),
 
             _ => {
                 if arg.starts_with("-"){
@@ -230,6 +703,15 @@ fn main() {
     if do_reverse {
         ps.reverse = ! ps.reverse;
     }
+    // Apply the shape options, regardless of their order
+    match &mut ps.path_creator {
+        PathCreator::Rays(o) | PathCreator::Circles(o) | PathCreator::Spiral(o) => *o = round_options,
+        PathCreator::Polygon { options, .. } | PathCreator::Star { options, .. } => *options = round_options,
+        _ => {}
+    }
+    if polar {
+        ps.polar = Some(round_options);
+    }
 
 
     /// Tries to open a image or reads from stdin
@@ -252,6 +734,15 @@ fn main() {
         }
     }
 
+    /// Replaces the thresholds with the ones suggested from the image statistics
+    fn apply_threshold_suggestion(ps: &mut pixelsortery::Pixelsorter, suggestion: Option<ThresholdSuggestion>, img: &RgbImage) {
+        if let (Some(method), PixelSelector::Threshold { criteria, .. }) = (suggestion, ps.selector) {
+            let (min, max) = analysis::suggest_threshold(img, criteria, method);
+            info!("Suggested thresholds ({:?}): {} - {}", method, min, max);
+            ps.selector = PixelSelector::Threshold { min, max, criteria };
+        }
+    }
+
     // Start gui with set options
     if start_gui {
         // TODO: give optional output path
@@ -260,6 +751,7 @@ fn main() {
         } else {
             // Exit if the input file is not an image
             if let Some(img) =  load_image(&input_path) {
+                apply_threshold_suggestion(&mut ps, threshold_suggestion, &img);
                 gui::init(Some(&ps), Some((img, PathBuf::from(&input_path))), None).unwrap();
             } else {
                 gui::init(Some(&ps), None, Some(PathBuf::from(&input_path))).unwrap();
@@ -303,12 +795,19 @@ fn main() {
             }
             exit(0);
         }
+        if threshold_suggestion.is_some() {
+            warn!("Automatic thresholds are not supported for videos, using the default range instead");
+        }
         println!("=== Input file could not be opened as an image. Starting Video mode! ===\n");
         ps.sort_video(&input_path, &output_path);
         return;
     }
 
     let mut img = img.unwrap();
+    apply_threshold_suggestion(&mut ps, threshold_suggestion, &img);
+    if show_coverage {
+        println!("Coverage of {}: {}", ps.path_creator, ps.coverage(&img));
+    }
 
 
     // SORTING WITHOUT A GUI! //
@@ -318,7 +817,7 @@ fn main() {
     if show_mask {
         // Drawing a mask
         if ! ps.mask(&mut img) {
-            error!("Couldn't create mask. Masking is only possible with the threshold selector.");
+            error!("Couldn't create mask. Masking is only possible with the threshold, hysteresis, edges or noise selector.");
             exit(-1);
         }
     } else {
@@ -0,0 +1,117 @@
+//! Small, seedable noise functions, so we don't need to pull in a whole crate for it
+
+/// Hashes a lattice point together with a seed into a pseudo random number (splitmix64)
+fn hash(x: i64, y: i64, seed: u64) -> u64 {
+    let mut z = seed
+        .wrapping_add((x as u64).wrapping_mul(0x9E3779B97F4A7C15))
+        .wrapping_add((y as u64).wrapping_mul(0xC2B2AE3D27D4EB4F));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// Maps a hash to a value in the range [-1, 1]
+fn hash_to_unit(h: u64) -> f64 {
+    (h >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+/// One dimensional perlin (gradient) noise in the range [0, 1]
+pub fn perlin_1d(x: f64, seed: u64) -> f64 {
+    let x0 = x.floor();
+    let t = x - x0;
+    let g0 = hash_to_unit(hash(x0 as i64, 0, seed));
+    let g1 = hash_to_unit(hash(x0 as i64 + 1, 0, seed));
+    // The gradient noise lies in [-0.5, 0.5]
+    (lerp(g0 * t, g1 * (t - 1.0), fade(t)) + 0.5).clamp(0.0, 1.0)
+}
+
+/// Returns the gradient of a lattice point as a unit vector
+fn gradient_2d(x: i64, y: i64, seed: u64) -> (f64, f64) {
+    let angle = hash_to_unit(hash(x, y, seed)) * std::f64::consts::PI;
+    (angle.cos(), angle.sin())
+}
+
+/// Two dimensional perlin noise in the range [0, 1]
+pub fn perlin_2d(x: f64, y: f64, seed: u64) -> f64 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (x - x0, y - y0);
+    let (xi, yi) = (x0 as i64, y0 as i64);
+
+    let dot = |gx: i64, gy: i64, dx: f64, dy: f64| {
+        let (g0, g1) = gradient_2d(gx, gy, seed);
+        g0 * dx + g1 * dy
+    };
+    let top = lerp(dot(xi, yi, tx, ty), dot(xi + 1, yi, tx - 1.0, ty), fade(tx));
+    let bottom = lerp(dot(xi, yi + 1, tx, ty - 1.0), dot(xi + 1, yi + 1, tx - 1.0, ty - 1.0), fade(tx));
+    // 2D perlin noise lies in [-sqrt(0.5), sqrt(0.5)]
+    (lerp(top, bottom, fade(ty)) * std::f64::consts::SQRT_2 * 0.5 + 0.5).clamp(0.0, 1.0)
+}
+
+/// Two dimensional simplex noise in the range [0, 1]
+pub fn simplex_2d(x: f64, y: f64, seed: u64) -> f64 {
+    const F2: f64 = 0.36602540378443865; // (sqrt(3) - 1) / 2
+    const G2: f64 = 0.21132486540518713; // (3 - sqrt(3)) / 6
+
+    // Skew into the simplex grid to find the cell
+    let s = (x + y) * F2;
+    let (i, j) = ((x + s).floor(), (y + s).floor());
+    let t = (i + j) * G2;
+    let (x0, y0) = (x - (i - t), y - (j - t));
+    // Which of the two triangles of the cell are we in
+    let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+    let corners = [
+        (0, 0, x0, y0),
+        (i1, j1, x0 - i1 as f64 + G2, y0 - j1 as f64 + G2),
+        (1, 1, x0 - 1.0 + 2.0 * G2, y0 - 1.0 + 2.0 * G2),
+    ];
+
+    let n: f64 = corners
+        .iter()
+        .map(|&(ci, cj, dx, dy)| {
+            let t = 0.5 - dx * dx - dy * dy;
+            if t < 0.0 {
+                return 0.0;
+            }
+            let (gx, gy) = gradient_2d(i as i64 + ci, j as i64 + cj, seed);
+            t.powi(4) * (gx * dx + gy * dy)
+        })
+        .sum();
+    // Scale the result roughly into [-1, 1]
+    (n * 70.0 * 0.5 + 0.5).clamp(0.0, 1.0)
+}
+
+/// Two dimensional worley (cellular) noise in the range [0, 1].
+/// Returns the distance to the nearest of the randomly placed points, one per cell
+pub fn worley_2d(x: f64, y: f64, seed: u64) -> f64 {
+    let (xi, yi) = (x.floor() as i64, y.floor() as i64);
+    let mut nearest = f64::MAX;
+    for cy in yi - 1..=yi + 1 {
+        for cx in xi - 1..=xi + 1 {
+            let px = cx as f64 + (hash_to_unit(hash(cx, cy, seed)) + 1.0) * 0.5;
+            let py = cy as f64 + (hash_to_unit(hash(cx, cy, seed ^ 0x5DEECE66D)) + 1.0) * 0.5;
+            nearest = nearest.min((px - x).hypot(py - y));
+        }
+    }
+    nearest.clamp(0.0, 1.0)
+}
+
+/// Sums up multiple octaves of a noise function, each with double the frequency and half the amplitude.
+/// Returns a value in the range [0, 1]
+pub fn fractal_2d(noise: fn(f64, f64, u64) -> f64, x: f64, y: f64, octaves: u32, seed: u64) -> f64 {
+    let (mut sum, mut amplitude, mut frequency, mut total) = (0.0, 1.0, 1.0, 0.0);
+    for octave in 0..octaves.max(1) {
+        sum += noise(x * frequency, y * frequency, seed.wrapping_add(octave as u64)) * amplitude;
+        total += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    sum / total
+}
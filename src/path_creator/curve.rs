@@ -0,0 +1,284 @@
+use log::error;
+use rayon::prelude::*;
+use std::f64::consts::{E, FRAC_PI_2, PI};
+
+/// Function that moves every row of a curve path to the side
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CurveFunction {
+    /// The original x³ like curve
+    Atan,
+    Sine,
+    Parabola,
+    /// A formula of `t`, f.e. "sin(t * pi) * t^2"
+    Expression(Formula),
+}
+
+impl CurveFunction {
+    /// Returns the displacement at t (0 is the center row, +-1 the top and bottom rows without scaling)
+    fn displacement(&self, t: f64) -> Result<f64, String> {
+        match self {
+            CurveFunction::Atan => Ok(t.atan() / FRAC_PI_2),
+            CurveFunction::Sine => Ok((t * PI).sin()),
+            CurveFunction::Parabola => Ok(t * t),
+            CurveFunction::Expression(formula) => formula.eval(t),
+        }
+    }
+}
+
+impl std::fmt::Display for CurveFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CurveFunction::Atan => write!(f, "atan"),
+            CurveFunction::Sine => write!(f, "sine"),
+            CurveFunction::Parabola => write!(f, "parabola"),
+            CurveFunction::Expression(formula) => write!(f, "{}", formula),
+        }
+    }
+}
+
+const FORMULA_LEN: usize = 64;
+
+/// The text of a formula. It has a fixed maximum length, so path creators can stay `Copy`
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Formula {
+    text: [u8; FORMULA_LEN],
+    len: usize,
+}
+
+impl Formula {
+    /// Returns None if the text is longer than 64 bytes. The syntax is only checked when evaluating
+    pub fn new(text: &str) -> Option<Formula> {
+        if text.len() > FORMULA_LEN {
+            return None;
+        }
+        let mut formula = Formula { text: [0; FORMULA_LEN], len: text.len() };
+        formula.text[..text.len()].copy_from_slice(text.as_bytes());
+        Some(formula)
+    }
+
+    pub fn as_str(&self) -> &str {
+        // Only ever created from a &str
+        std::str::from_utf8(&self.text[..self.len]).unwrap_or_default()
+    }
+
+    /// Evaluates the formula with the given value for `t`.
+    /// Supports + - * / ^, parentheses, the constants pi and e, and common functions like sin, sqrt or abs
+    pub fn eval(&self, t: f64) -> Result<f64, String> {
+        let mut parser = Parser { text: self.as_str().as_bytes(), pos: 0, t };
+        let value = parser.expression()?;
+        parser.skip_whitespace();
+        match parser.peek() {
+            None => Ok(value),
+            Some(c) => Err(format!("Unexpected '{}' at position {}", c as char, parser.pos)),
+        }
+    }
+}
+
+impl std::fmt::Debug for Formula {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl std::fmt::Display for Formula {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Small recursive descent parser that evaluates while parsing
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+    t: f64,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.pos).copied()
+    }
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+    /// Skips whitespace and consumes the char, if it is the next one
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    // expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<f64, String> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat(b'+') {
+                value += self.term()?;
+            } else if self.eat(b'-') {
+                value -= self.term()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    // term := unary (('*' | '/') unary)*
+    fn term(&mut self) -> Result<f64, String> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat(b'*') {
+                value *= self.unary()?;
+            } else if self.eat(b'/') {
+                value /= self.unary()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    // unary := '-' unary | power
+    fn unary(&mut self) -> Result<f64, String> {
+        if self.eat(b'-') {
+            return Ok(-self.unary()?);
+        }
+        self.power()
+    }
+
+    // power := atom ('^' unary)?
+    fn power(&mut self) -> Result<f64, String> {
+        let base = self.atom()?;
+        if self.eat(b'^') {
+            return Ok(base.powf(self.unary()?));
+        }
+        Ok(base)
+    }
+
+    // atom := number | '(' expression ')' | name | name '(' expression ')'
+    fn atom(&mut self) -> Result<f64, String> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek() {
+            Some(b'(') => {
+                self.pos += 1;
+                let value = self.expression()?;
+                if !self.eat(b')') {
+                    return Err(format!("Missing ')' at position {}", self.pos));
+                }
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() || c == b'.' => {
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || c == b'.') {
+                    self.pos += 1;
+                }
+                let number = std::str::from_utf8(&self.text[start..self.pos]).unwrap_or_default();
+                number.parse().map_err(|_| format!("Invalid number '{}'", number))
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                while self.peek().is_some_and(|c| c.is_ascii_alphanumeric()) {
+                    self.pos += 1;
+                }
+                let name = std::str::from_utf8(&self.text[start..self.pos]).unwrap_or_default();
+                match name {
+                    "t" => return Ok(self.t),
+                    "pi" => return Ok(PI),
+                    "e" => return Ok(E),
+                    _ => {}
+                }
+                let function: fn(f64) -> f64 = match name {
+                    "sin" => f64::sin,
+                    "cos" => f64::cos,
+                    "tan" => f64::tan,
+                    "asin" => f64::asin,
+                    "acos" => f64::acos,
+                    "atan" => f64::atan,
+                    "sqrt" => f64::sqrt,
+                    "abs" => f64::abs,
+                    "exp" => f64::exp,
+                    "ln" => f64::ln,
+                    "floor" => f64::floor,
+                    "ceil" => f64::ceil,
+                    "round" => f64::round,
+                    _ => return Err(format!("Unknown name '{}'", name)),
+                };
+                if !self.eat(b'(') {
+                    return Err(format!("Missing '(' after '{}'", name));
+                }
+                let value = self.expression()?;
+                if !self.eat(b')') {
+                    return Err(format!("Missing ')' at position {}", self.pos));
+                }
+                Ok(function(value))
+            }
+            Some(c) => Err(format!("Unexpected '{}' at position {}", c as char, self.pos)),
+            None => Err("Unexpected end of formula".into()),
+        }
+    }
+}
+
+/// Goes through the image column by column, moving every row of a column to the side by the curve function.
+/// `t` is 0 at the center row (moved by the offset) and +-`scale` at the top and bottom rows,
+/// the displacement is multiplied by `width` pixels.
+/// Rows where the function is not finite (f.e. 1/t at t = 0 or ln(t) for t <= 0) stay in place
+pub(crate) fn path_curve(w: u64, h: u64, function: CurveFunction, offset: i32, width: f32, scale: f32) -> Vec<Vec<u64>> {
+    let h2: f64 = h as f64 / 2.0;
+    let displacements: Vec<i64> = match (0..h)
+        .map(|y| {
+            let t = (y as f64 - h2 - offset as f64) / (h2 / scale as f64);
+            function.displacement(t).map(|d| {
+                let d = if d.is_finite() { d * width as f64 } else { 0.0 };
+                // Moving a row further than the image is wide only wraps it into other rows, and keeps the sums below from overflowing
+                d.clamp(-(w as f64), w as f64) as i64
+            })
+        })
+        .collect()
+    {
+        Ok(displacements) => displacements,
+        Err(e) => {
+            error!("Could not evaluate the curve function: {}", e);
+            vec![0; h as usize]
+        }
+    };
+
+    // Indices outside of the image are skipped when picking the pixels
+    let index = |x: i64, y: i64| y.saturating_mul(w as i64).saturating_add(x);
+    (0..w)
+        .into_par_iter()
+        .map(|x| {
+            (0..h)
+                .map(|y| {
+                    // Some unsigned/signed stuff
+                    index((x as i64).saturating_add(displacements[y as usize]), y as i64) as u64
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poles_and_huge_widths_dont_overflow() {
+        for text in ["1/t", "tan(t * pi / 2)", "ln(t)", "0/0"] {
+            let function = CurveFunction::Expression(Formula::new(text).unwrap());
+            for width in [200.0, f32::MAX] {
+                let paths = path_curve(20, 10, function, 0, width, 1.0);
+                assert_eq!(paths.len(), 20);
+                assert!(paths.iter().all(|p| p.len() == 10));
+            }
+        }
+    }
+
+    #[test]
+    fn formula_evaluation() {
+        let formula = Formula::new("sin(t * pi / 2) + 2^3 - abs(-1)").unwrap();
+        assert!((formula.eval(1.0).unwrap() - 8.0).abs() < 1e-9);
+        assert!(Formula::new("sin(t").unwrap().eval(0.0).is_err());
+        assert!(Formula::new("foo(t)").unwrap().eval(0.0).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+use rayon::prelude::*;
+
+/// Goes through the rows, alternating between left to right and right to left
+pub(crate) fn path_serpentine(w: u64, h: u64) -> Vec<Vec<u64>> {
+    let path = (0..h)
+        .flat_map(|y| {
+            let row = (y * w..y * w + w);
+            if y % 2 == 0 {
+                row.collect::<Vec<_>>()
+            } else {
+                row.rev().collect()
+            }
+        })
+        .collect();
+    vec![path]
+}
+
+/// Interleaves the bits of x and y, the z-order curve just goes through these codes in order
+fn morton_code(x: u64, y: u64) -> u64 {
+    // Spreads the lower 32 bits of a number onto the even bits
+    fn spread(mut v: u64) -> u64 {
+        v &= 0xFFFF_FFFF;
+        v = (v | (v << 16)) & 0x0000_FFFF_0000_FFFF;
+        v = (v | (v << 8)) & 0x00FF_00FF_00FF_00FF;
+        v = (v | (v << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+        v = (v | (v << 2)) & 0x3333_3333_3333_3333;
+        v = (v | (v << 1)) & 0x5555_5555_5555_5555;
+        v
+    }
+    spread(x) | (spread(y) << 1)
+}
+
+/// The z-order (morton) curve. Codes outside of the image are skipped,
+/// so it works on any size, but jumps at the borders of non power of two images
+pub(crate) fn path_z_order(w: u64, h: u64) -> Vec<Vec<u64>> {
+    let mut pixels: Vec<(u64, u64)> = (0..w * h)
+        .into_par_iter()
+        .map(|i| (morton_code(i % w, i / w), i))
+        .collect();
+    pixels.par_sort_unstable_by_key(|&(code, _)| code);
+    vec![pixels.into_iter().map(|(_, i)| i).collect()]
+}
+
+/// Splits a length into (at most) three parts.
+/// Odd lengths get split into odd parts, so the curve in every part ends on the opposite side it started on
+fn split_in_three(len: u64) -> Vec<u64> {
+    let base = len / 3;
+    let parts = match (len % 2 == 1, base % 2 == 1, len % 3) {
+        (true, true, 2) => [base + 2, base, base],
+        (true, false, 1) if base > 0 => [base + 1, base + 1, base - 1],
+        _ => [0, 1, 2].map(|i| base + (i < len % 3) as u64),
+    };
+    parts.into_iter().filter(|&l| l > 0).collect()
+}
+
+/// Fills the rectangle with a peano curve: It gets split into 3x3 blocks, which are visited column by column,
+/// going down the first column, up the second one and so on. Every block is filled the same way,
+/// but mirrored so it starts next to where the previous one ended.
+/// Rectangles that can't be split evenly get blocks of slightly different sizes,
+/// even sizes can't be filled without some jumps though
+fn generate_peano(coords: &mut Vec<(u64, u64)>, x: u64, y: u64, w: u64, h: u64, flip_x: bool, flip_y: bool) {
+    if w == 1 && h == 1 {
+        coords.push((x, y));
+        return;
+    }
+
+    // Start and size of every column and row
+    let parts = |start: u64, len: u64, flip: bool| {
+        let mut offset = start;
+        let mut parts: Vec<(u64, u64)> = split_in_three(len)
+            .into_iter()
+            .map(|l| {
+                offset += l;
+                (offset - l, l)
+            })
+            .collect();
+        if flip {
+            parts.reverse();
+        }
+        parts
+    };
+
+    for (col, &(bx, bw)) in parts(x, w, flip_x).iter().enumerate() {
+        let col_flip_y = flip_y ^ (col % 2 == 1);
+        for (row, &(by, bh)) in parts(y, h, col_flip_y).iter().enumerate() {
+            generate_peano(coords, bx, by, bw, bh, flip_x ^ (row % 2 == 1), col_flip_y);
+        }
+    }
+}
+
+pub(crate) fn path_peano(w: u64, h: u64) -> Vec<Vec<u64>> {
+    let mut path = Vec::new();
+    if w > 0 && h > 0 {
+        generate_peano(&mut path, 0, 0, w, h, false, false);
+    }
+    vec![path.into_iter().map(|(x, y)| y * w + x).collect()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path_creator::test_helpers::{assert_covers_once, assert_no_jumps};
+
+    const SIZES: [(u64, u64); 7] = [(1, 1), (1, 6), (7, 1), (4, 4), (5, 8), (16, 9), (27, 13)];
+
+    #[test]
+    fn curves_cover_every_pixel_once() {
+        for (w, h) in SIZES {
+            assert_covers_once(&path_serpentine(w, h), w, h);
+            assert_covers_once(&path_z_order(w, h), w, h);
+            assert_covers_once(&path_peano(w, h), w, h);
+        }
+    }
+
+    #[test]
+    fn serpentine_has_no_jumps() {
+        for (w, h) in SIZES {
+            assert_no_jumps(&path_serpentine(w, h)[0], w);
+        }
+    }
+
+    #[test]
+    fn peano_has_no_jumps_on_odd_sizes() {
+        for w in (1..=29).step_by(2) {
+            for h in (1..=29).step_by(2) {
+                assert_no_jumps(&path_peano(w, h)[0], w);
+            }
+        }
+    }
+
+    #[test]
+    fn odd_lengths_split_into_odd_parts() {
+        for len in (1..200).step_by(2) {
+            let parts = split_in_three(len);
+            assert_eq!(parts.iter().sum::<u64>(), len);
+            assert!(parts.iter().all(|l| l % 2 == 1), "{} is split into {:?}", len, parts);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use image::Rgb;
+use rayon::prelude::*;
+use std::ops::Deref;
+
+use crate::{gradient, noise};
+
+/// Where the vector field of the flow paths comes from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlowSource {
+    /// Follows the edges of the image. The structure is smoothed with the given radius in pixels
+    Image { smoothing: u32 },
+    /// Follows a perlin noise field, `scale` is the size of a noise cell in pixels
+    Noise { scale: u32, seed: u64 },
+}
+
+/// Returns a unit vector for every pixel
+fn vector_field<P: Deref<Target = Rgb<u8>> + Sync>(pixels: &[P], w: u64, h: u64, source: FlowSource) -> Vec<(f32, f32)> {
+    match source {
+        FlowSource::Image { smoothing } => gradient::edge_directions(pixels, w, h, smoothing as u64),
+        FlowSource::Noise { scale, seed } => {
+            let scale = scale.max(1) as f64;
+            (0..w * h)
+                .into_par_iter()
+                .map(|i| {
+                    let (x, y) = ((i % w) as f64 / scale, (i / w) as f64 / scale);
+                    // The noise rarely leaves [0.25, 0.75], so let that range turn a full circle
+                    let angle = (noise::perlin_2d(x, y, seed) * 4.0 * std::f64::consts::PI) as f32;
+                    (angle.cos(), angle.sin())
+                })
+                .collect()
+        }
+    }
+}
+
+/// Traces streamlines through the vector field. Every pixel is part of exactly one streamline:
+/// A streamline starts at the first pixel that isn't part of one yet and grows in both directions
+/// until it leaves the image or runs into another streamline
+pub(crate) fn path_flow<P: Deref<Target = Rgb<u8>> + Sync>(pixels: &[P], w: u64, h: u64, source: FlowSource) -> Vec<Vec<u64>> {
+    let field = vector_field(pixels, w, h, source);
+    let mut visited = vec![false; (w * h) as usize];
+
+    // Follows the field from the start pixel, marking and returning the pixels on the way (without the start)
+    let trace = |start: u64, sign: f32, visited: &mut Vec<bool>| {
+        let mut line = Vec::new();
+        let (mut x, mut y) = ((start % w) as f32 + 0.5, (start / w) as f32 + 0.5);
+        let (mut last_dx, mut last_dy) = (0.0, 0.0);
+        let mut current = start;
+        loop {
+            let (mut dx, mut dy) = field[current as usize];
+            // Field directions have no sign, keep going the way we came
+            let flip = if line.is_empty() { sign } else if dx * last_dx + dy * last_dy < 0.0 { -1.0 } else { 1.0 };
+            (dx, dy) = (dx * flip, dy * flip);
+            (last_dx, last_dy) = (dx, dy);
+
+            // Step until we reach the next pixel
+            let mut next = current;
+            for _ in 0..4 {
+                x += dx * 0.5;
+                y += dy * 0.5;
+                if x < 0.0 || y < 0.0 || x >= w as f32 || y >= h as f32 {
+                    return line;
+                }
+                next = y as u64 * w + x as u64;
+                if next != current {
+                    break;
+                }
+            }
+            if next == current || visited[next as usize] {
+                return line;
+            }
+            visited[next as usize] = true;
+            line.push(next);
+            current = next;
+        }
+    };
+
+    let mut paths = Vec::new();
+    for start in 0..w * h {
+        if visited[start as usize] {
+            continue;
+        }
+        visited[start as usize] = true;
+        let mut path = trace(start, -1.0, &mut visited);
+        path.reverse();
+        path.push(start);
+        path.extend(trace(start, 1.0, &mut visited));
+        paths.push(path);
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path_creator::test_helpers::assert_covers_once;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn streamlines_cover_every_pixel_once() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for (w, h) in [(1u64, 1u64), (1, 12), (12, 1), (29, 21)] {
+            let flat = vec![Rgb([90, 90, 90]); (w * h) as usize];
+            let noise: Vec<Rgb<u8>> = (0..w * h).map(|_| Rgb(rng.gen())).collect();
+            let rings: Vec<Rgb<u8>> = (0..w * h).map(|i| Rgb([(((i % w).pow(2) + (i / w).pow(2)) * 8 % 256) as u8; 3])).collect();
+            for colors in [flat, noise, rings] {
+                let pixels: Vec<&Rgb<u8>> = colors.iter().collect();
+                for source in [
+                    FlowSource::Image { smoothing: 0 },
+                    FlowSource::Image { smoothing: 4 },
+                    FlowSource::Noise { scale: 0, seed: 0 },
+                    FlowSource::Noise { scale: 16, seed: 7 },
+                ] {
+                    assert_covers_once(&path_flow(&pixels, w, h, source), w, h);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn flat_images_flow_along_the_columns() {
+        // Without any edges, the structure tensor is zero and the direction falls back to straight down
+        let (w, h) = (7, 5);
+        let colors = vec![Rgb([200, 10, 10]); (w * h) as usize];
+        let pixels: Vec<&Rgb<u8>> = colors.iter().collect();
+        let mut paths = path_flow(&pixels, w, h, FlowSource::Image { smoothing: 2 });
+        paths.iter_mut().for_each(|p| p.sort());
+        paths.sort();
+        let columns: Vec<Vec<u64>> = (0..w).map(|x| (0..h).map(|y| y * w + x).collect()).collect();
+        assert_eq!(paths, columns);
+    }
+}
@@ -25,107 +25,199 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-pub(crate) fn path_hilbert(width: u64, height: u64) -> Vec<Vec<u64>> {
-    // Most of this code here is copied/translated from here: https://github.com/jakubcerveny/gilbert/blob/master/gilbert2d.py
-    // Which i translated to C at some point and then translated that C code to rust
-
-    fn sgn(x: i64) -> i64 {
-        if x < 0 {
-            -1
-        } else if x > 0 {
-            1
-        } else {
-            0
-        }
-    };
+use rayon::prelude::*;
 
-    /// To bring a bit of glitchiness into it, switch ay and bx as parameters (or any other and try experimenting!)
-    fn generate2dhilbert(
-        coords: &mut Vec<(u64, u64)>,
-        mut x: i64,
-        mut y: i64,
-        ax: i64,
-        ay: i64,
-        bx: i64,
-        by: i64,
-    ) {
-        // width and height
-        let w = (ax + ay).abs();
-        let h = (bx + by).abs();
-
-        let dax = sgn(ax);
-        let day = sgn(ay);
-        let dbx = sgn(bx);
-        let dby = sgn(by);
-
-
-        if (h == 1) {
-            // trivial row fill
-            for i in 0..w {
-                coords.push((x as u64, y as u64));
-                x += dax;
-                y += day;
-            }
-            return;
-        }
+// Most of this code here is copied/translated from here: https://github.com/jakubcerveny/gilbert/blob/master/gilbert2d.py
+// Which i translated to C at some point and then translated that C code to rust
 
-        if (w == 1) {
-            // trivial column fill
-            for i in 0..h {
-                coords.push((x as u64, y as u64));
-                x += dbx;
-                y += dby;
-            }
-            return;
+fn sgn(x: i64) -> i64 {
+    if x < 0 {
+        -1
+    } else if x > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+/// To bring a bit of glitchiness into it, switch ay and bx as parameters (or any other and try experimenting!)
+fn generate2dhilbert(
+    coords: &mut Vec<(u64, u64)>,
+    mut x: i64,
+    mut y: i64,
+    ax: i64,
+    ay: i64,
+    bx: i64,
+    by: i64,
+) {
+    // width and height
+    let w = (ax + ay).abs();
+    let h = (bx + by).abs();
+
+    let dax = sgn(ax);
+    let day = sgn(ay);
+    let dbx = sgn(bx);
+    let dby = sgn(by);
+
+
+    if (h == 1) {
+        // trivial row fill
+        for i in 0..w {
+            coords.push((x as u64, y as u64));
+            x += dax;
+            y += day;
         }
+        return;
+    }
 
-        let mut ax2 = ax / 2;
-        let mut ay2 = ay / 2;
-        let mut bx2 = bx / 2;
-        let mut by2 = by / 2;
+    if (w == 1) {
+        // trivial column fill
+        for i in 0..h {
+            coords.push((x as u64, y as u64));
+            x += dbx;
+            y += dby;
+        }
+        return;
+    }
 
-        let w2 = (ax2 + ay2).abs();
-        let h2 = (bx2 + by2).abs();
+    let mut ax2 = ax / 2;
+    let mut ay2 = ay / 2;
+    let mut bx2 = bx / 2;
+    let mut by2 = by / 2;
 
-        if (2 * w > 3 * h) {
-            if (w2 % 2 != 0 && w > 2) {
-                // prefer even steps
-                ax2 += dax;
-                ay2 += day;
-            }
+    let w2 = (ax2 + ay2).abs();
+    let h2 = (bx2 + by2).abs();
 
-            // long case: split in two parts only
-            generate2dhilbert(coords, x, y, ax2, ay2, bx, by);
-            generate2dhilbert(coords, x + ax2, y + ay2, ax - ax2, ay - ay2, bx, by);
-        } else {
-            if (h2 % 2 != 0 && h > 2) {
-                // prefer even steps
-                bx2 += dbx;
-                by2 += dby;
-            }
+    if (2 * w > 3 * h) {
+        if (w2 % 2 != 0 && w > 2) {
+            // prefer even steps
+            ax2 += dax;
+            ay2 += day;
+        }
 
-            // standard case: one step up, one long horizontal, one step down
-            generate2dhilbert(coords, x, y, bx2, by2, ax2, ay2);
-            generate2dhilbert(coords, x + bx2, y + by2, ax, ay, bx - bx2, by - by2);
-            generate2dhilbert(
-                coords,
-                x + (ax - dax) + (bx2 - dbx),
-                y + (ay - day) + (by2 - dby),
-                -bx2,
-                -by2,
-                -(ax - ax2),
-                -(ay - ay2),
-            );
+        // long case: split in two parts only
+        generate2dhilbert(coords, x, y, ax2, ay2, bx, by);
+        generate2dhilbert(coords, x + ax2, y + ay2, ax - ax2, ay - ay2, bx, by);
+    } else {
+        if (h2 % 2 != 0 && h > 2) {
+            // prefer even steps
+            bx2 += dbx;
+            by2 += dby;
         }
-    };
 
-    let mut path = Vec::new();
-    if (width >= height) {
-        generate2dhilbert(&mut path, 0, 0, width as i64, 0, 0, height as i64)
+        // standard case: one step up, one long horizontal, one step down
+        generate2dhilbert(coords, x, y, bx2, by2, ax2, ay2);
+        generate2dhilbert(coords, x + bx2, y + by2, ax, ay, bx - bx2, by - by2);
+        generate2dhilbert(
+            coords,
+            x + (ax - dax) + (bx2 - dbx),
+            y + (ay - day) + (by2 - dby),
+            -bx2,
+            -by2,
+            -(ax - ax2),
+            -(ay - ay2),
+        );
+    }
+}
+
+/// Fills the rectangle at (x, y) with a hilbert curve, going along the longer side
+/// (or the shorter one, if `transposed` is set)
+fn hilbert_block(coords: &mut Vec<(u64, u64)>, x: u64, y: u64, width: u64, height: u64, transposed: bool) {
+    let (x, y, w, h) = (x as i64, y as i64, width as i64, height as i64);
+    if ((width >= height) != transposed) {
+        generate2dhilbert(coords, x, y, w, 0, 0, h)
     } else {
-        generate2dhilbert(&mut path, 0, 0, 0, height as i64, width as i64, 0)
+        generate2dhilbert(coords, x, y, 0, h, w, 0)
     };
+}
+
+pub(crate) fn path_hilbert(width: u64, height: u64) -> Vec<Vec<u64>> {
+    let mut path = Vec::new();
+    hilbert_block(&mut path, 0, 0, width, height, false);
 
     vec![path.into_iter().map(|(x,y)| y*width+x).collect()]
 }
 
+/// Cuts the image into blocks of the given size, each one filled by its own hilbert curve.
+/// With `alternate`, every other block (like on a checkerboard) goes along the other side
+pub(crate) fn path_hilbert_tiled(width: u64, height: u64, block_size: u64, alternate: bool) -> Vec<Vec<u64>> {
+    let block_size = block_size.max(1);
+    let (blocks_x, blocks_y) = (width.div_ceil(block_size), height.div_ceil(block_size));
+
+    (0..blocks_x * blocks_y)
+        .into_par_iter()
+        .map(|b| {
+            let (bx, by) = (b % blocks_x, b / blocks_x);
+            let (x, y) = (bx * block_size, by * block_size);
+            let (w, h) = (block_size.min(width - x), block_size.min(height - y));
+            let mut path = Vec::new();
+            hilbert_block(&mut path, x, y, w, h, alternate && (bx + by) % 2 == 1);
+            path.into_iter().map(|(x, y)| y * width + x).collect()
+        })
+        .collect()
+}
+
+/// The moore curve is the closed loop version of the hilbert curve.
+/// The image is cut in a left and a right half, the left one is filled upwards and the right one downwards.
+/// Both curves start and end at the middle line, so the path ends right next to where it started
+pub(crate) fn path_moore(width: u64, height: u64) -> Vec<Vec<u64>> {
+    let left = width / 2;
+    if left == 0 {
+        return path_hilbert(width, height);
+    }
+    let (left, right, h) = (left as i64, (width - width / 2) as i64, height as i64);
+
+    let mut path = Vec::new();
+    generate2dhilbert(&mut path, left - 1, h - 1, 0, -h, -left, 0);
+    generate2dhilbert(&mut path, left, 0, 0, h, right, 0);
+
+    vec![path.into_iter().map(|(x,y)| y*width+x).collect()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path_creator::test_helpers::{assert_covers_once, assert_no_jumps};
+
+    #[test]
+    fn hilbert_tiles_cover_every_pixel_once() {
+        // Blocks that divide the image, partial blocks at the border and one block larger than the image
+        for (w, h) in [(1, 1), (10, 10), (23, 17)] {
+            for block_size in [0, 1, 5, 30] {
+                for alternate in [false, true] {
+                    let blocks = path_hilbert_tiled(w, h, block_size, alternate);
+                    assert_covers_once(&blocks, w, h);
+                    for block in &blocks {
+                        assert_no_jumps(block, w);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn alternate_blocks_go_along_the_other_side() {
+        let blocks = path_hilbert_tiled(8, 4, 4, false);
+        let alternated = path_hilbert_tiled(8, 4, 4, true);
+        assert_eq!(blocks[0], alternated[0]);
+        assert_ne!(blocks[1], alternated[1]);
+    }
+
+    #[test]
+    fn moore_covers_every_pixel_once() {
+        for (w, h) in [(1, 1), (1, 6), (7, 1), (4, 4), (5, 8), (16, 9), (27, 13)] {
+            assert_covers_once(&path_moore(w, h), w, h);
+        }
+    }
+
+    #[test]
+    fn moore_is_a_closed_loop() {
+        for size in [2, 4, 8, 32] {
+            let path = path_moore(size, size).remove(0);
+            assert_no_jumps(&path, size);
+            // The end is next to the start
+            let closed = [path[path.len() - 1], path[0]];
+            assert_no_jumps(&closed, size);
+        }
+    }
+}
@@ -2,9 +2,25 @@ use egui::TextBuffer;
 use image::{Rgb, RgbImage};
 use log::{error, info, warn};
 use rayon::prelude::*;
-use std::{f64::consts::PI, fmt::Display, time::Instant};
+use std::{f64::consts::PI, fmt::Display, ops::Deref, time::Instant};
 
+use crate::PixelSpan;
+
+mod curve;
+mod curves;
+mod flow;
 mod gilbert;
+mod regions;
+mod strokes;
+mod tiles;
+mod walk;
+
+pub use curve::{CurveFunction, Formula};
+pub use flow::FlowSource;
+pub use regions::RegionOrder;
+pub use strokes::Stroke;
+pub(crate) use strokes::path_strokes;
+pub use tiles::{TileDirection, TileDirections, TileOrder};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PathCreator {
@@ -12,14 +28,146 @@ pub enum PathCreator {
     AllVertically,
     HorizontalLines,
     VerticalLines,
-    Rays,
-    Circles,
-    Spiral,
+    Rays(RoundOptions),
+    Circles(RoundOptions),
+    Spiral(RoundOptions),
     SquareSpiral,
     RectSpiral,
     Diagonally(f32),
-    Hilbert,
-    X3(i32, i64, f64),
+    /// Rotates the image by the angle (in degrees, 0° goes to the right and 90° down), sorts the rows
+    /// and rotates the image back. Slower than `Diagonally`, but makes smooth lines at any angle
+    Rotated(f32),
+    /// With a block size, the image is cut into square blocks, each filled by its own curve.
+    /// `alternate` switches the direction of every other block
+    Hilbert {
+        block_size: Option<u32>,
+        alternate: bool,
+    },
+    Moore,
+    Peano,
+    ZOrder,
+    /// Goes through the rows like `AllHorizontally`, but every other row backwards
+    Serpentine,
+    /// Goes through the columns, moving every row to the side by a function of the row.
+    /// The offset moves the center of the curve up or down, the width scales the displacement (in pixels)
+    /// and the scale stretches the function vertically
+    Curve {
+        function: CurveFunction,
+        offset: i32,
+        width: f32,
+        scale: f32,
+    },
+    /// Lines following a sine curve, tilted by the angle (in degrees). The phase is in degrees, too
+    Waves {
+        amplitude: f32,
+        wavelength: f32,
+        phase: f32,
+        angle: f32,
+    },
+    /// Concentric regular polygons, rotated by the angle (in degrees). At 0° a corner points up.
+    /// They have at least 3 sides and use the center and the ellipse of the options
+    Polygon {
+        sides: u32,
+        rotation: f32,
+        options: RoundOptions,
+    },
+    /// Concentric stars. `inner` is the ratio between the inner and the outer radius.
+    /// They have at least 3 points and use the center and the ellipse of the options
+    Star {
+        points: u32,
+        inner: f32,
+        rotation: f32,
+        options: RoundOptions,
+    },
+    /// Streamlines following a vector field, taken from the image or from noise
+    Flow(FlowSource),
+    /// Voronoi cells around points on a grid with a spacing of `size` pixels, randomly moved by `jitter` (0 to 1).
+    /// The pixels inside every cell are ordered by `order`
+    Voronoi {
+        size: u32,
+        jitter: f32,
+        order: RegionOrder,
+        seed: u64,
+    },
+    /// Superpixels of about `size` pixels, which follow the colors of the image.
+    /// A higher `compactness` makes them rounder. The pixels inside every segment are ordered by `order`
+    Segments {
+        size: u32,
+        compactness: f32,
+        order: RegionOrder,
+    },
+    /// Sorts along the strokes of the `Pixelsorter`, which are filled with parallel lines `width` pixels wide
+    Strokes { width: u32 },
+    /// Square tiles of `size` pixels, each one sorted in a direction from the list
+    Tiles {
+        size: u32,
+        directions: TileDirections,
+        order: TileOrder,
+    },
+    /// Self-avoiding random walks, which prefer steps towards the angle (in degrees, 0° goes to the right and 90° down).
+    /// A `bias` of 0 makes every step equally likely
+    RandomWalk { angle: f32, bias: f32, seed: u64 },
+}
+
+/// Shape of the round paths (Circles, Spiral and Rays, also Polygons and Stars).
+/// Rays only use the center, as they are straight lines anyway. Polygons and stars have their own rotation instead of the start angle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoundOptions {
+    /// Center relative to the image size, (0.5, 0.5) is the middle of the image
+    pub center: (f32, f32),
+    /// Ratio between the x and the y radius, 1.0 makes circles
+    pub ellipse: f32,
+    /// The angle (in degrees) where a circle starts, -90° is the top
+    pub start_angle: f32,
+}
+
+impl Default for RoundOptions {
+    fn default() -> Self {
+        RoundOptions {
+            center: (0.5, 0.5),
+            ellipse: 1.0,
+            start_angle: -90.0,
+        }
+    }
+}
+
+impl RoundOptions {
+    /// Returns the center in pixels
+    pub(crate) fn center_px(&self, w: u64, h: u64) -> (f64, f64) {
+        (w as f64 * self.center.0 as f64, h as f64 * self.center.1 as f64)
+    }
+    /// Returns the factors the radius is scaled with in x and y direction
+    pub(crate) fn radius_scale(&self) -> (f64, f64) {
+        let ratio = (self.ellipse as f64).max(0.01).sqrt();
+        (ratio, 1.0 / ratio)
+    }
+}
+
+/// How well the paths of a path creator cover an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Coverage {
+    /// Amount of pixels in the image
+    pub pixels: u64,
+    /// Pixels that are not part of any path, these are never sorted
+    pub uncovered: u64,
+    /// Additional visits of pixels that are already part of a path. They get dropped when picking pixels
+    pub duplicates: u64,
+    /// Indices outside of the image
+    pub out_of_bounds: u64,
+}
+
+impl std::fmt::Display for Coverage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {} pixels covered ({} uncovered, {} duplicate visits, {} out of bounds)",
+            self.pixels - self.uncovered,
+            self.pixels,
+            self.uncovered,
+            self.duplicates,
+            self.out_of_bounds
+        )
+    }
 }
 
 impl std::fmt::Display for PathCreator {
@@ -32,14 +180,32 @@ impl std::fmt::Display for PathCreator {
                 PathCreator::AllVertically => "All Vertically".into(),
                 PathCreator::HorizontalLines => "Left/Right".into(),
                 PathCreator::VerticalLines => "Up/Down".into(),
-                PathCreator::Rays => "Rays".into(),
-                PathCreator::Circles => "Circles".into(),
-                PathCreator::Spiral => "Spiral".into(),
+                PathCreator::Rays(_) => "Rays".into(),
+                PathCreator::Circles(_) => "Circles".into(),
+                PathCreator::Spiral(_) => "Spiral".into(),
                 PathCreator::SquareSpiral => "Square Spiral".into(),
                 PathCreator::RectSpiral => "Rectangular Spiral".into(),
                 PathCreator::Diagonally(a) => format!("Diagonally ({}°)", a),
-                PathCreator::Hilbert => "Hilbert Curve".into(),
-                PathCreator::X3(_, _, _) => "x³".into(),
+                PathCreator::Rotated(a) => format!("Rotated ({}°)", a),
+                PathCreator::Hilbert { block_size: None, .. } => "Hilbert Curve".into(),
+                PathCreator::Hilbert { block_size: Some(_), .. } => "Hilbert Tiles".into(),
+                PathCreator::Moore => "Moore Curve".into(),
+                PathCreator::Peano => "Peano Curve".into(),
+                PathCreator::ZOrder => "Z-Order Curve".into(),
+                PathCreator::Serpentine => "Serpentine".into(),
+                PathCreator::Curve { function: CurveFunction::Atan, .. } => "x³".into(),
+                PathCreator::Curve { function: CurveFunction::Expression(_), .. } => "Curve (Formula)".into(),
+                PathCreator::Curve { function, .. } => format!("Curve ({})", function),
+                PathCreator::Waves { .. } => "Waves".into(),
+                PathCreator::Polygon { .. } => "Polygons".into(),
+                PathCreator::Star { .. } => "Stars".into(),
+                PathCreator::Flow(FlowSource::Image { .. }) => "Flow (Image)".into(),
+                PathCreator::Flow(FlowSource::Noise { .. }) => "Flow (Noise)".into(),
+                PathCreator::Voronoi { .. } => "Voronoi".into(),
+                PathCreator::Segments { .. } => "Segments".into(),
+                PathCreator::Strokes { .. } => "Strokes".into(),
+                PathCreator::Tiles { .. } => "Tiles".into(),
+                PathCreator::RandomWalk { .. } => "Random Walk".into(),
             }
         )
     }
@@ -49,29 +215,13 @@ impl PathCreator {
     pub fn info_string(self) -> String {
         format!("Direction/Order: [{:?}]", self)
     }
-    pub fn create_paths(self, all_pixels: Vec<&mut Rgb<u8>>, w: u64, h: u64, reverse: bool) -> Vec<Vec<&mut Rgb<u8>>> {
+    /// Cuts the image into paths of pixels. Every pixel is paired with its index (y * w + x) in the image
+    pub fn create_paths(self, all_pixels: Vec<&mut Rgb<u8>>, w: u64, h: u64, reverse: bool) -> Vec<PixelSpan<'_>> {
 
         let mut total_timestart = Instant::now();
         let mut timestart = Instant::now();
 
-        // Actual path algorithms
-        // Ideas/missing:
-        // In waves
-        // In star shape
-        let mut all_paths_indices = match self {
-            PathCreator::AllHorizontally => path_all_horizontally(w, h),
-            PathCreator::AllVertically => path_all_vertically(w, h),
-            PathCreator::HorizontalLines => path_horizontal_lines(w, h),
-            PathCreator::VerticalLines => path_vertical_lines(w, h),
-            PathCreator::Rays => path_rays(w, h),
-            PathCreator::SquareSpiral => path_rect_spiral(w, h, true),
-            PathCreator::RectSpiral => path_rect_spiral(w, h, false),
-            PathCreator::Diagonally(angle) => path_diagonal_lines(w, h, angle),
-            PathCreator::Circles => path_circles(w, h),
-            PathCreator::Spiral => path_round_spiral(w, h),
-            PathCreator::Hilbert => gilbert::path_hilbert(w, h),
-            PathCreator::X3(offset_y, scalar_w, scalar_l) => path_x3(w, h, offset_y, scalar_w as f64, scalar_l as f64),
-        };
+        let mut all_paths_indices = self.create_index_paths(&all_pixels, w, h);
         let timeend_pathing = timestart.elapsed();
         timestart = Instant::now();
 
@@ -98,12 +248,91 @@ impl PathCreator {
 
         return pixels;
     }
+
+    /// Returns the paths as indices (y * w + x) of the pixels.
+    /// Only some paths look at the pixels, the others just need the size
+    fn create_index_paths<P: Deref<Target = Rgb<u8>> + Sync>(self, all_pixels: &[P], w: u64, h: u64) -> Vec<Vec<u64>> {
+        // Actual path algorithms
+        match self {
+            PathCreator::AllHorizontally => path_all_horizontally(w, h),
+            PathCreator::AllVertically => path_all_vertically(w, h),
+            PathCreator::HorizontalLines => path_horizontal_lines(w, h),
+            PathCreator::VerticalLines => path_vertical_lines(w, h),
+            PathCreator::Rays(options) => path_rays(w, h, options),
+            PathCreator::SquareSpiral => path_rect_spiral(w, h, true),
+            PathCreator::RectSpiral => path_rect_spiral(w, h, false),
+            PathCreator::Diagonally(angle) => path_diagonal_lines(w, h, angle),
+            // The rotation happens while sorting, on the rotated image it's just rows
+            PathCreator::Rotated(_) => path_horizontal_lines(w, h),
+            PathCreator::Circles(options) => path_circles(w, h, options),
+            PathCreator::Spiral(options) => path_round_spiral(w, h, options),
+            PathCreator::Hilbert { block_size: None, .. } => gilbert::path_hilbert(w, h),
+            PathCreator::Hilbert { block_size: Some(size), alternate } => {
+                gilbert::path_hilbert_tiled(w, h, size as u64, alternate)
+            }
+            PathCreator::Moore => gilbert::path_moore(w, h),
+            PathCreator::Peano => curves::path_peano(w, h),
+            PathCreator::ZOrder => curves::path_z_order(w, h),
+            PathCreator::Serpentine => curves::path_serpentine(w, h),
+            PathCreator::Curve { function, offset, width, scale } => curve::path_curve(w, h, function, offset, width, scale),
+            PathCreator::Waves { amplitude, wavelength, phase, angle } => path_waves(w, h, amplitude, wavelength, phase, angle),
+            // A star whose inner corners lie on the edges of the polygon is just the polygon
+            PathCreator::Polygon { sides, rotation, options } => {
+                let sides = sides.max(MIN_CORNERS);
+                path_star(w, h, sides, (PI / sides as f64).cos() as f32, rotation, options)
+            }
+            PathCreator::Star { points, inner, rotation, options } => path_star(w, h, points, inner, rotation, options),
+            PathCreator::Flow(source) => flow::path_flow(all_pixels, w, h, source),
+            PathCreator::Voronoi { size, jitter, order, seed } => {
+                regions::region_paths(&regions::voronoi_labels(w, h, size, jitter, seed), w, order, seed)
+            }
+            PathCreator::Segments { size, compactness, order } => {
+                regions::region_paths(&regions::slic_labels(all_pixels, w, h, size, compactness), w, order, 0)
+            }
+            // The strokes are part of the Pixelsorter, see `path_strokes`
+            PathCreator::Strokes { .. } => Vec::new(),
+            PathCreator::Tiles { size, directions, order } => tiles::path_tiles(w, h, size, directions, order),
+            PathCreator::RandomWalk { angle, bias, seed } => walk::path_random_walk(w, h, angle, bias, seed),
+        }
+    }
+
+    /// Checks how well the paths cover the image: How many pixels are never visited or visited more than once.
+    /// Strokes are drawn on the Pixelsorter, so their coverage is only available from there (see `Pixelsorter::coverage`)
+    pub fn coverage(self, img: &RgbImage) -> Option<Coverage> {
+        if let PathCreator::Strokes { .. } = self {
+            return None;
+        }
+        let (w, h) = (img.width() as u64, img.height() as u64);
+        let all_pixels: Vec<&Rgb<u8>> = img.pixels().collect();
+        Some(Coverage::of(&self.create_index_paths(&all_pixels, w, h), w, h))
+    }
+}
+
+impl Coverage {
+    /// Counts how often the paths visit every pixel of an image with the given size
+    pub(crate) fn of(paths: &[Vec<u64>], w: u64, h: u64) -> Coverage {
+        let mut visits = vec![0u32; (w * h) as usize];
+        let mut coverage = Coverage { pixels: w * h, ..Default::default() };
+        for &i in paths.iter().flatten() {
+            match visits.get_mut(i as usize) {
+                Some(v) => *v += 1,
+                None => coverage.out_of_bounds += 1,
+            }
+        }
+        for v in visits {
+            match v {
+                0 => coverage.uncovered += 1,
+                v => coverage.duplicates += (v - 1) as u64,
+            }
+        }
+        coverage
+    }
 }
 
-/// Creates and returns ranges of mutable Pixels.
+/// Creates and returns ranges of mutable Pixels, each paired with its index.
 /// The picked pixels and their order are determined by the given vector of indices
-fn pick_pixels(all_pixels: Vec<&mut Rgb<u8>>, indices: Vec<Vec<u64>>) -> Vec<Vec<&mut Rgb<u8>>> {
-    let mut paths: Vec<Vec<&mut Rgb<u8>>> = Vec::new();
+pub(crate) fn pick_pixels(all_pixels: Vec<&mut Rgb<u8>>, indices: Vec<Vec<u64>>) -> Vec<PixelSpan<'_>> {
+    let mut paths: Vec<PixelSpan<'_>> = Vec::new();
     let mut all_pixels: Vec<Option<&mut Rgb<u8>>> =
         all_pixels.into_iter().map(|p| Some(p)).collect();
 
@@ -116,7 +345,7 @@ fn pick_pixels(all_pixels: Vec<&mut Rgb<u8>>, indices: Vec<Vec<u64>>) -> Vec<Vec
                 all_pixels.push(None);
                 // Check if the pixel at index i is still available (not None)
                 if let Some(px) = all_pixels.swap_remove(i as usize) {
-                    path.push(px);
+                    path.push((i, px));
                 }
             }
         }
@@ -126,7 +355,7 @@ fn pick_pixels(all_pixels: Vec<&mut Rgb<u8>>, indices: Vec<Vec<u64>>) -> Vec<Vec
 }
 
 fn is_in_bounds(x: u64, y: u64, w: u64, h: u64) -> bool {
-    x > 0 && x < w && y > 0 && y < h
+    x < w && y < h
 }
 
 fn path_all_horizontally(w: u64, h: u64) -> Vec<Vec<u64>> {
@@ -167,20 +396,70 @@ fn path_vertical_lines(w: u64, h: u64) -> Vec<Vec<u64>> {
 }
 
 const TWO_PI: f64 = 2.0 * PI;
-const HALF_PI: f64 = PI * 0.5;
-fn path_x3(w: u64, h: u64, offset_y: i32, scalar_w: f64, scalar_l: f64) -> Vec<Vec<u64>> {
-    let index = |x: i64, y: i64| y * w as i64 + x;
-    (0..w).into_par_iter()
-        .map(|x| {
-            (0..h).into_par_iter().map(|y|{
-                // let y2: f64 = (y - (h / 2)) as f64 / 50.0;
-                let h2: f64 = h as f64/2.0;
-                let y2: f64 = (y as f64 - h2 - offset_y as f64) / (h2 / scalar_l);
-                let res = (y2.atan() * scalar_w / HALF_PI);
-                // Some unsigned/signed stuff
-                index(x as i64 + res as i64 , y as i64) as u64
-            }).collect()
-        }).collect()
+fn path_waves(w: u64, h: u64, amplitude: f32, wavelength: f32, phase: f32, angle: f32) -> Vec<Vec<u64>> {
+    // Every pixel gets a position t along the base direction and a position s across it.
+    // Shifting s by the wave (at t) and rounding it gives the line the pixel belongs to,
+    // so every pixel belongs to exactly one line
+    let (sin, cos) = (angle as f64).to_radians().sin_cos();
+    let (amplitude, phase) = (amplitude as f64, (phase as f64).to_radians());
+    let wavelength = (wavelength as f64).max(1.0);
+
+    let mut pixels: Vec<(i64, f64, u64)> = (0..w * h)
+        .into_par_iter()
+        .map(|i| {
+            let (x, y) = ((i % w) as f64, (i / w) as f64);
+            let t = x * cos + y * sin;
+            let s = y * cos - x * sin;
+            let line = (s - amplitude * (TWO_PI * t / wavelength + phase).sin()).round() as i64;
+            (line, t, i)
+        })
+        .collect();
+    pixels.par_sort_unstable_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+
+    pixels
+        .chunk_by(|a, b| a.0 == b.0)
+        .map(|line| line.iter().map(|&(_, _, i)| i).collect())
+        .collect()
+}
+
+/// The fewest sides of a polygon and points of a star
+const MIN_CORNERS: u32 = 3;
+fn path_star(w: u64, h: u64, points: u32, inner: f32, rotation: f32, options: RoundOptions) -> Vec<Vec<u64>> {
+    // Like the waves, every pixel is assigned to the outline it lies on,
+    // by comparing its distance to the center with the radius of the outline in its direction.
+    // The outlines are then walked clockwise, starting at the top corner
+    let points = points.max(MIN_CORNERS) as f64;
+    let inner = (inner as f64).clamp(0.01, 1.0);
+    let (cx, cy) = options.center_px(w, h);
+    let (sx, sy) = options.radius_scale();
+    let start = (rotation as f64 - 90.0).to_radians();
+    let segment = PI / points;
+
+    // Radius of the outline with an outer radius of 1, in the direction of the angle (relative to a corner).
+    // Intersects the ray with the edge from the outer corner (1, 0) to the inner corner at the angle `segment`
+    let outline_radius = |angle: f64| {
+        let angle = angle.rem_euclid(2.0 * segment);
+        let angle = if angle > segment { 2.0 * segment - angle } else { angle };
+        inner * segment.sin() / (inner * (segment - angle).sin() + angle.sin())
+    };
+
+    let mut pixels: Vec<(u64, f64, u64)> = (0..w * h)
+        .into_par_iter()
+        .map(|i| {
+            // Use the center of the pixel and undo the stretching of the ellipse
+            let dx = ((i % w) as f64 + 0.5 - cx) / sx;
+            let dy = ((i / w) as f64 + 0.5 - cy) / sy;
+            let angle = (dy.atan2(dx) - start).rem_euclid(TWO_PI);
+            let outline = (dx.hypot(dy) / outline_radius(angle)).round() as u64;
+            (outline, angle, i)
+        })
+        .collect();
+    pixels.par_sort_unstable_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+
+    pixels
+        .chunk_by(|a, b| a.0 == b.0)
+        .map(|outline| outline.iter().map(|&(_, _, i)| i).collect())
+        .collect()
 }
 
 fn path_diagonal_lines(w: u64, h: u64, angle: f32) -> Vec<Vec<u64>> {
@@ -273,9 +552,9 @@ fn spread_range(start: u64, end: u64) -> Vec<u64> {
 }
 
 
-fn path_rays(w: u64, h: u64) -> Vec<Vec<u64>> {
-    let mut cx:f32 = (w as f32) / 2.0;
-    let mut cy:f32 = (h as f32) / 2.0;
+fn path_rays(w: u64, h: u64, options: RoundOptions) -> Vec<Vec<u64>> {
+    let (cx, cy) = options.center_px(w, h);
+    let (cx, cy) = (cx as f32, cy as f32);
     let mut dirs: Vec<(f32, f32)> = Vec::new();;
     let mut tips: Vec<(f32, f32)>;
     let  mut y = 0; let mut x = 0;
@@ -284,11 +563,13 @@ fn path_rays(w: u64, h: u64) -> Vec<Vec<u64>> {
     tips.extend(spread_range(0, w-1).into_iter().map(|x| { (x as f32, h as f32) }));
     tips.extend(spread_range(0, h-1).into_iter().map(|y| { (0.0, y as f32) }));
     tips.extend(spread_range(0, h-1).into_iter().map(|y| { (w as f32, y as f32) }));
-    dirs = tips.into_iter().map(|(x,y)| {
+    dirs = tips.into_iter().filter_map(|(x,y)| {
         let dx = x - cx;
         let dy = y - cy;
         let m = 2.0 * dx.abs().max(dy.abs());
-        (dx/m, dy/m)
+        // A tip can lie on the center, if the center is on the border
+        if m == 0.0 { return None; }
+        Some((dx/m, dy/m))
     }).collect();
     let ray = |(dx, dy): (f32, f32)| {
         let mut x = cx;
@@ -297,9 +578,9 @@ fn path_rays(w: u64, h: u64) -> Vec<Vec<u64>> {
         loop {
             x += dx;
             y += dy;
-            let i: u64 = y.round() as u64 * w + x.round() as u64;
-            path.push(i);
-            if x < 0.0 || x.ceil() > w as f32  || y < 0.0 || y.ceil() > h as f32 { break; }
+            let (xr, yr) = (x.round(), y.round());
+            if xr < 0.0 || yr < 0.0 || !is_in_bounds(xr as u64, yr as u64, w, h) { break; }
+            path.push(yr as u64 * w + xr as u64);
         }
         path
     };
@@ -369,77 +650,151 @@ fn path_rect_spiral(w: u64, h: u64, square: bool) -> Vec<Vec<u64>> {
     return paths;
 }
 
+/// Assigns every pixel to the ring (ellipse) it lies on and sorts them by ring and by angle.
+/// Returns (ring, angle, index), the angle is in [0, 2π) and starts at the start angle
+fn polar_rings(w: u64, h: u64, options: RoundOptions) -> Vec<(u64, f64, u64)> {
+    let (cx, cy) = options.center_px(w, h);
+    let (sx, sy) = options.radius_scale();
+    let angle_offset = (options.start_angle as f64).to_radians();
+
+    let mut pixels: Vec<(u64, f64, u64)> = (0..w * h)
+        .into_par_iter()
+        .map(|i| {
+            // Use the center of the pixel and undo the stretching of the ellipse
+            let dx = ((i % w) as f64 + 0.5 - cx) / sx;
+            let dy = ((i / w) as f64 + 0.5 - cy) / sy;
+            let angle = (dy.atan2(dx) - angle_offset).rem_euclid(TWO_PI);
+            (dx.hypot(dy).round() as u64, angle, i)
+        })
+        .collect();
+    pixels.par_sort_unstable_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    pixels
+}
+
 // Not really a spiral, more like connected circles
-fn path_round_spiral(w: u64, h: u64) -> Vec<Vec<u64>> {
-    let mut paths: Vec<Vec<u64>> = Vec::new();
-    let mut x = w as f64 / 2.0;
-    let mut y = h as f64 / 2.0;
-    let pixelcount = w * h;
-    // The max radius has to be
-    let max_size = ((w * w + h * h) as f64).sqrt().ceil() as u64;
+fn path_round_spiral(w: u64, h: u64, options: RoundOptions) -> Vec<Vec<u64>> {
+    vec![polar_rings(w, h, options).into_iter().map(|(_, _, i)| i).collect()]
+}
 
-    let mut r = 1.0;
-    let angle_offset = -0.5 * PI;
+fn path_circles(w: u64, h: u64, options: RoundOptions) -> Vec<Vec<u64>> {
+    // Every circle is split into two halves, both starting at the start angle and going in opposite directions
+    polar_rings(w, h, options)
+        .chunk_by(|a, b| a.0 == b.0)
+        .flat_map(|ring| {
+            let half = ring.partition_point(|&(_, angle, _)| angle <= PI);
+            let path_left = ring[..half].iter().map(|&(_, _, i)| i).collect();
+            let path_right = ring[half..].iter().rev().map(|&(_, _, i)| i).collect();
+            [path_left, path_right]
+        })
+        .collect()
+}
 
-    // TODO: let angle_offset be set
-    // TODO: make elliptic, not just circles
-    // TODO: Allow to set center
+/// Helpers for the tests of the path creators
+#[cfg(test)]
+pub(crate) mod test_helpers {
+    /// Asserts that every pixel of the image is part of exactly one path
+    pub(crate) fn assert_covers_once(paths: &[Vec<u64>], w: u64, h: u64) {
+        let mut seen = vec![0; (w * h) as usize];
+        for &i in paths.iter().flatten() {
+            assert!(i < w * h, "index {} is outside of the {}x{} image", i, w, h);
+            seen[i as usize] += 1;
+        }
+        for (i, n) in seen.iter().enumerate() {
+            assert_eq!(*n, 1, "pixel {} is covered {} times", i, n);
+        }
+    }
 
-    let line_path = |r| {
-        let mut path = Vec::new();
-        let step_amounts = 16 * r as u64;
-        let step_size: f64 = 2.0 * PI / step_amounts as f64;
-        for step in 0..=step_amounts {
-            let angle = angle_offset + step_size * step as f64;
-            let xi = x + angle.cos() * r as f64;
-            let yi = y + angle.sin() * r as f64;
-            if !is_in_bounds(xi as u64, yi as u64, w, h) {
-                continue;
+    /// Asserts that every step of the path goes to a neighbouring pixel (no diagonals)
+    pub(crate) fn assert_no_jumps(path: &[u64], w: u64) {
+        for step in path.windows(2) {
+            let (x0, y0) = ((step[0] % w) as i64, (step[0] / w) as i64);
+            let (x1, y1) = ((step[1] % w) as i64, (step[1] / w) as i64);
+            assert_eq!((x1 - x0).abs() + (y1 - y0).abs(), 1, "jump from {:?} to {:?}", (x0, y0), (x1, y1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_helpers::assert_covers_once;
+
+    /// Paths of path creators that don't look at the pixels
+    fn index_paths(path: PathCreator, w: u64, h: u64) -> Vec<Vec<u64>> {
+        path.create_index_paths::<&Rgb<u8>>(&[], w, h)
+    }
+
+    #[test]
+    fn waves_cover_every_pixel_once() {
+        for (w, h) in [(1, 1), (9, 4), (33, 20)] {
+            for (amplitude, wavelength, angle) in [(0.0, 200.0, 0.0), (20.0, 7.0, 30.0), (3.0, 0.0, -135.0), (50.0, 1.0, 90.0)] {
+                let waves = PathCreator::Waves { amplitude, wavelength, phase: 45.0, angle };
+                assert_covers_once(&index_paths(waves, w, h), w, h);
             }
-            path.push(yi as u64 * w + xi as u64);
         }
-        path
-    };
+    }
 
-    // THREADING, WOOO
-    let path_iter = (1..max_size / 2).into_par_iter().map(line_path);
-    paths = vec![path_iter.flatten().collect()];
-    return paths;
-}
+    #[test]
+    fn flat_waves_are_rows() {
+        let waves = PathCreator::Waves { amplitude: 0.0, wavelength: 100.0, phase: 0.0, angle: 0.0 };
+        assert_eq!(index_paths(waves, 5, 3), index_paths(PathCreator::HorizontalLines, 5, 3));
+    }
 
-fn path_circles(w: u64, h: u64) -> Vec<Vec<u64>> {
-    let mut paths: Vec<Vec<u64>> = Vec::new();
-    let mut x = w as f64 / 2.0;
-    let mut y = h as f64 / 2.0;
-    let pixelcount = w * h;
-    // The max radius has to be
-    let max_size = ((w * w + h * h) as f64).sqrt().ceil() as u64;
-
-    let mut r = 1;
-    let angle_offset = -0.5 * PI;
-
-    // TODO: let angle_offset be set
-    // TODO: make elliptic, not just circles
-    // TODO: Allow to set center
-
-    let line_path = |r| {
-        let mut path_left = Vec::new();
-        let mut path_right = Vec::new();
-        let step_amounts = 8 * r as u64;
-        let circ_step_size: f64 = PI / step_amounts as f64;
-        for step in 0..=step_amounts {
-            let angle = angle_offset + circ_step_size * step as f64;
-            let xi = x + angle.cos() * r as f64;
-            let yi = y + angle.sin() * r as f64;
-            path_left.push(yi as u64 * w + xi as u64);
-            let angle = angle_offset - circ_step_size * step as f64;
-            let xi = x + angle.cos() * r as f64;
-            let yi = y + angle.sin() * r as f64;
-            path_right.push(yi as u64 * w + xi as u64);
+    #[test]
+    fn circles_and_spirals_cover_every_pixel_once() {
+        let stretched = RoundOptions { center: (0.9, 0.2), ellipse: 0.3, start_angle: 45.0 };
+        for (w, h) in [(1, 1), (8, 3), (31, 20)] {
+            for options in [RoundOptions::default(), stretched] {
+                assert_covers_once(&index_paths(PathCreator::Circles(options), w, h), w, h);
+                assert_covers_once(&index_paths(PathCreator::Spiral(options), w, h), w, h);
+            }
         }
-        vec![path_left, path_right]
-    };
-    // THREADING, WOOO
-    paths.par_extend((1..max_size / 2).into_par_iter().map(line_path).flatten());
-    return paths;
+    }
+
+    #[test]
+    fn polygons_and_stars_cover_every_pixel_once() {
+        let off_center = RoundOptions { center: (0.1, 0.8), ellipse: 2.5, ..Default::default() };
+        for (w, h) in [(1, 1), (7, 3), (40, 25)] {
+            for corners in [0, 2, 3, 7] {
+                for options in [RoundOptions::default(), off_center] {
+                    let polygon = PathCreator::Polygon { sides: corners, rotation: 10.0, options };
+                    let star = PathCreator::Star { points: corners, inner: 0.3, rotation: 10.0, options };
+                    assert_covers_once(&index_paths(polygon, w, h), w, h);
+                    assert_covers_once(&index_paths(star, w, h), w, h);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn polygons_and_stars_use_the_center() {
+        let options = RoundOptions { center: (0.25, 0.5), ..Default::default() };
+        let (w, h) = (40, 20);
+        for path in [
+            PathCreator::Polygon { sides: 5, rotation: 0.0, options },
+            PathCreator::Star { points: 5, inner: 0.5, rotation: 0.0, options },
+        ] {
+            // The innermost outline lies around the center at (10, 10)
+            let paths = index_paths(path, w, h);
+            for &i in &paths[0] {
+                let (x, y) = ((i % w) as i64, (i / w) as i64);
+                assert!((x - 10).abs() <= 2 && (y - 10).abs() <= 2, "{:?} is far from the center", (x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn too_few_corners_are_clamped_the_same_way() {
+        let options = RoundOptions::default();
+        let (w, h) = (30, 30);
+        // A polygon is a star whose inner corners lie on its edges
+        let inner = (PI / MIN_CORNERS as f64).cos() as f32;
+        let triangle = index_paths(PathCreator::Polygon { sides: MIN_CORNERS, rotation: 0.0, options }, w, h);
+        for corners in [0, 1, 2] {
+            let polygon = PathCreator::Polygon { sides: corners, rotation: 0.0, options };
+            let star = PathCreator::Star { points: corners, inner, rotation: 0.0, options };
+            assert_eq!(index_paths(polygon, w, h), triangle);
+            assert_eq!(index_paths(star, w, h), triangle);
+        }
+    }
 }
@@ -0,0 +1,331 @@
+use image::Rgb;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
+use std::{f64::consts::PI, ops::Deref};
+
+use crate::color_helpers::get_lab;
+
+/// How the pixels inside of a region (f.e. a voronoi cell) are ordered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionOrder {
+    /// Every row of a region is a path
+    Rows,
+    /// Parallel lines at a random angle, different for every region
+    RandomAngle,
+    /// One path per region, spiraling outwards from its center
+    Spiral,
+}
+
+/// Cuts the image into regions (given by a label for every pixel) and creates paths inside each of them
+pub(crate) fn region_paths(labels: &[u32], w: u64, order: RegionOrder, seed: u64) -> Vec<Vec<u64>> {
+    let regions = labels.iter().max().map_or(0, |&l| l as usize + 1);
+    let mut region_pixels: Vec<Vec<u64>> = vec![Vec::new(); regions];
+    for (i, &label) in labels.iter().enumerate() {
+        region_pixels[label as usize].push(i as u64);
+    }
+
+    region_pixels
+        .into_par_iter()
+        .enumerate()
+        .filter(|(_, pixels)| !pixels.is_empty())
+        .flat_map_iter(|(region, pixels)| match order {
+            // The pixels are already sorted by rows, only cut where a row (or a part of it) ends
+            RegionOrder::Rows => pixels.chunk_by(|a, b| a + 1 == *b && a / w == b / w).map(|r| r.to_vec()).collect(),
+            RegionOrder::RandomAngle => {
+                let angle = StdRng::seed_from_u64(seed ^ region as u64).gen_range(0.0..PI);
+                region_lines(&pixels, w, angle)
+            }
+            RegionOrder::Spiral => vec![region_spiral(&pixels, w)],
+        })
+        .collect()
+}
+
+/// Cuts the pixels of a region into parallel lines at the angle (in radians)
+fn region_lines(pixels: &[u64], w: u64, angle: f64) -> Vec<Vec<u64>> {
+    let (sin, cos) = angle.sin_cos();
+    let mut lines: Vec<(i64, f64, u64)> = pixels
+        .iter()
+        .map(|&i| {
+            let (x, y) = ((i % w) as f64, (i / w) as f64);
+            ((y * cos - x * sin).round() as i64, x * cos + y * sin, i)
+        })
+        .collect();
+    lines.sort_unstable_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    lines.chunk_by(|a, b| a.0 == b.0).map(|l| l.iter().map(|&(_, _, i)| i).collect()).collect()
+}
+
+/// Orders the pixels of a region in rings around its center
+fn region_spiral(pixels: &[u64], w: u64) -> Vec<u64> {
+    let n = pixels.len() as f64;
+    let cx = pixels.iter().map(|&i| (i % w) as f64).sum::<f64>() / n;
+    let cy = pixels.iter().map(|&i| (i / w) as f64).sum::<f64>() / n;
+    let mut rings: Vec<(u64, f64, u64)> = pixels
+        .iter()
+        .map(|&i| {
+            let (dx, dy) = ((i % w) as f64 - cx, (i / w) as f64 - cy);
+            (dx.hypot(dy).round() as u64, dy.atan2(dx), i)
+        })
+        .collect();
+    rings.sort_unstable_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    rings.into_iter().map(|(_, _, i)| i).collect()
+}
+
+/// Returns the voronoi cell of every pixel. The points are placed on a grid with a spacing of `size` pixels,
+/// each one moved randomly inside of its grid cell by up to `jitter` (0 to 1) times the size
+pub(crate) fn voronoi_labels(w: u64, h: u64, size: u32, jitter: f32, seed: u64) -> Vec<u32> {
+    let size = size.max(1) as u64;
+    let (gw, gh) = (w.div_ceil(size) as i64, h.div_ceil(size) as i64);
+    let points = voronoi_points(gw, gh, size, jitter, seed);
+
+    (0..w * h)
+        .into_par_iter()
+        .map(|i| {
+            let (x, y) = ((i % w) as f64 + 0.5, (i / w) as f64 + 0.5);
+            let (gx, gy) = ((i % w / size) as i64, (i / w / size) as i64);
+            // The nearest point has to be in one of the neighbouring grid cells
+            let mut nearest = (f64::MAX, 0);
+            for py in (gy - 2).max(0)..(gy + 3).min(gh) {
+                for px in (gx - 2).max(0)..(gx + 3).min(gw) {
+                    let p = py * gw + px;
+                    let (dx, dy) = (points[p as usize].0 - x, points[p as usize].1 - y);
+                    let dist = dx * dx + dy * dy;
+                    if dist < nearest.0 {
+                        nearest = (dist, p as u32);
+                    }
+                }
+            }
+            nearest.1
+        })
+        .collect()
+}
+
+/// The points of the voronoi cells, one inside of every cell of the grid (row by row).
+/// The jitter is clamped (and ignored if it's not finite), so no point leaves its grid cell
+fn voronoi_points(gw: i64, gh: i64, size: u64, jitter: f32, seed: u64) -> Vec<(f64, f64)> {
+    let jitter = if jitter.is_finite() { jitter.clamp(0.0, 1.0) as f64 } else { 0.0 };
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..gw * gh)
+        .map(|p| {
+            let (jx, jy): (f64, f64) = (rng.gen_range(-0.5..0.5), rng.gen_range(-0.5..0.5));
+            (
+                ((p % gw) as f64 + 0.5 + jx * jitter) * size as f64,
+                ((p / gw) as f64 + 0.5 + jy * jitter) * size as f64,
+            )
+        })
+        .collect()
+}
+
+/// Returns the superpixel of every pixel (SLIC). Starting from a grid with a spacing of `size` pixels,
+/// every pixel is assigned to the nearest center, weighting the color difference against the distance.
+/// A higher `compactness` makes the segments rounder, a lower one makes them follow the colors more closely
+pub(crate) fn slic_labels<P: Deref<Target = Rgb<u8>> + Sync>(pixels: &[P], w: u64, h: u64, size: u32, compactness: f32) -> Vec<u32> {
+    const ITERATIONS: usize = 10;
+    let size = size.max(2) as u64;
+    let (gw, gh) = (w.div_ceil(size) as i64, h.div_ceil(size) as i64);
+    let spatial_weight = (compactness as f64 / size as f64).powi(2);
+    let colors: Vec<(f64, f64, f64)> = pixels
+        .par_iter()
+        .map(|p| {
+            let (l, a, b) = get_lab(p);
+            (l as f64, a as f64, b as f64)
+        })
+        .collect();
+
+    // Every center is (x, y, l, a, b)
+    let mut centers: Vec<[f64; 5]> = (0..gw * gh)
+        .map(|c| {
+            let x = (((c % gw) as u64 * size + size / 2).min(w - 1)) as f64;
+            let y = (((c / gw) as u64 * size + size / 2).min(h - 1)) as f64;
+            let (l, a, b) = colors[(y as u64 * w + x as u64) as usize];
+            [x, y, l, a, b]
+        })
+        .collect();
+
+    let mut labels = vec![0u32; (w * h) as usize];
+    for _ in 0..ITERATIONS {
+        // Assign every pixel to the nearest center of the neighbouring grid cells
+        labels.par_iter_mut().enumerate().for_each(|(i, label)| {
+            let (x, y) = ((i as u64 % w) as f64, (i as u64 / w) as f64);
+            let (l, a, b) = colors[i];
+            let (gx, gy) = ((i as u64 % w / size) as i64, (i as u64 / w / size) as i64);
+            let mut nearest = f64::MAX;
+            for cy in (gy - 1).max(0)..(gy + 2).min(gh) {
+                for cx in (gx - 1).max(0)..(gx + 2).min(gw) {
+                    let c = (cy * gw + cx) as usize;
+                    let [px, py, pl, pa, pb] = centers[c];
+                    let color_dist = (l - pl).powi(2) + (a - pa).powi(2) + (b - pb).powi(2);
+                    let dist = color_dist + ((x - px).powi(2) + (y - py).powi(2)) * spatial_weight;
+                    if dist < nearest {
+                        nearest = dist;
+                        *label = c as u32;
+                    }
+                }
+            }
+        });
+
+        // Move every center to the mean of its pixels
+        let mut sums = vec![[0.0f64; 6]; centers.len()];
+        for (i, &label) in labels.iter().enumerate() {
+            let (l, a, b) = colors[i];
+            let s = &mut sums[label as usize];
+            for (k, v) in [(i as u64 % w) as f64, (i as u64 / w) as f64, l, a, b, 1.0].into_iter().enumerate() {
+                s[k] += v;
+            }
+        }
+        for (center, s) in centers.iter_mut().zip(sums) {
+            if s[5] > 0.0 {
+                *center = std::array::from_fn(|k| s[k] / s[5]);
+            }
+        }
+    }
+
+    enforce_connectivity(&labels, w, h, (size * size / 4) as usize)
+}
+
+/// Relabels the pixels so every region is connected. Parts smaller than `min_size` are merged into a neighbouring region
+fn enforce_connectivity(labels: &[u32], w: u64, h: u64, min_size: usize) -> Vec<u32> {
+    const NONE: u32 = u32::MAX;
+    let mut new_labels = vec![NONE; labels.len()];
+    let mut next_label = 0;
+    let mut stack = Vec::new();
+    let mut part = Vec::new();
+
+    for start in 0..labels.len() {
+        if new_labels[start] != NONE {
+            continue;
+        }
+        // Flood fill the part with the same old label
+        part.clear();
+        stack.push(start);
+        new_labels[start] = next_label;
+        let mut adjacent = None;
+        while let Some(i) = stack.pop() {
+            part.push(i);
+            let (x, y) = (i as u64 % w, i as u64 / w);
+            let neighbours = [
+                (x > 0).then(|| i - 1),
+                (x + 1 < w).then(|| i + 1),
+                (y > 0).then(|| i - w as usize),
+                (y + 1 < h).then(|| i + w as usize),
+            ];
+            for n in neighbours.into_iter().flatten() {
+                if labels[n] == labels[start] && new_labels[n] == NONE {
+                    new_labels[n] = next_label;
+                    stack.push(n);
+                } else if labels[n] != labels[start] && new_labels[n] != NONE {
+                    adjacent = Some(new_labels[n]);
+                }
+            }
+        }
+        // Small parts join a region next to them
+        match adjacent {
+            Some(adjacent) if part.len() < min_size => part.iter().for_each(|&i| new_labels[i] = adjacent),
+            _ => next_label += 1,
+        }
+    }
+    new_labels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path_creator::test_helpers::assert_covers_once;
+
+    const ORDERS: [RegionOrder; 3] = [RegionOrder::Rows, RegionOrder::RandomAngle, RegionOrder::Spiral];
+
+    #[test]
+    fn voronoi_paths_cover_every_pixel_once() {
+        for (w, h) in [(1, 1), (1, 9), (9, 1), (23, 17)] {
+            for size in [0, 1, 4, 40] {
+                for jitter in [0.0, 0.5, 1.0, 3.0, -1.0, f32::NAN] {
+                    let labels = voronoi_labels(w, h, size, jitter, 5);
+                    for order in ORDERS {
+                        assert_covers_once(&region_paths(&labels, w, order, 5), w, h);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn voronoi_cells_belong_to_the_nearest_point() {
+        let (w, h, size): (u64, u64, u64) = (31, 24, 5);
+        let (gw, gh) = (w.div_ceil(size) as i64, h.div_ceil(size) as i64);
+        for jitter in [0.0, 1.0, 10.0, f32::NAN] {
+            let labels = voronoi_labels(w, h, size as u32, jitter, 9);
+            let points = voronoi_points(gw, gh, size, jitter, 9);
+            for (i, &label) in labels.iter().enumerate() {
+                let (x, y) = ((i as u64 % w) as f64 + 0.5, (i as u64 / w) as f64 + 0.5);
+                let dist = |&(px, py): &(f64, f64)| (px - x).powi(2) + (py - y).powi(2);
+                let nearest = points.iter().map(dist).fold(f64::MAX, f64::min);
+                assert_eq!(dist(&points[label as usize]), nearest, "pixel {} is not in the nearest cell", i);
+            }
+        }
+    }
+
+    /// Asserts that the labels are numbered from 0 and that the pixels of every label are connected
+    fn assert_connected(labels: &[u32], w: u64) {
+        let regions = labels.iter().max().map_or(0, |&l| l as usize + 1);
+        let mut sizes = vec![0; regions];
+        labels.iter().for_each(|&l| sizes[l as usize] += 1);
+        assert!(sizes.iter().all(|&s| s > 0), "the labels have gaps");
+
+        for (label, size) in sizes.into_iter().enumerate() {
+            // Flood fill from the first pixel of the region, it has to reach all of them
+            let start = labels.iter().position(|&l| l as usize == label).unwrap();
+            let mut seen = vec![false; labels.len()];
+            let (mut stack, mut reached) = (vec![start], 0);
+            seen[start] = true;
+            while let Some(i) = stack.pop() {
+                reached += 1;
+                let (x, y) = (i as u64 % w, i as u64 / w);
+                let neighbours = [
+                    (x > 0).then(|| i - 1),
+                    (x + 1 < w).then(|| i + 1),
+                    (y > 0).then(|| i - w as usize),
+                    (i + (w as usize) < labels.len()).then(|| i + w as usize),
+                ];
+                for n in neighbours.into_iter().flatten() {
+                    if labels[n] as usize == label && !seen[n] {
+                        seen[n] = true;
+                        stack.push(n);
+                    }
+                }
+            }
+            assert_eq!(reached, size, "region {} is split", label);
+        }
+    }
+
+    #[test]
+    fn segments_are_connected_and_cover_every_pixel_once() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for (w, h) in [(1, 1), (1, 30), (30, 1), (25, 19)] {
+            let noise: Vec<Rgb<u8>> = (0..w * h).map(|_| Rgb(rng.gen())).collect();
+            let stripes: Vec<Rgb<u8>> = (0..w * h).map(|i| if (i % w + i / w) % 3 == 0 { Rgb([255, 0, 0]) } else { Rgb([0, 0, 255]) }).collect();
+            for colors in [noise, stripes] {
+                let pixels: Vec<&Rgb<u8>> = colors.iter().collect();
+                for (size, compactness) in [(0, 10.0), (3, 0.1), (6, 10.0), (100, 100.0)] {
+                    let labels = slic_labels(&pixels, w, h, size, compactness);
+                    assert_connected(&labels, w);
+                    for order in ORDERS {
+                        assert_covers_once(&region_paths(&labels, w, order, 0), w, h);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn small_parts_are_merged_into_connected_regions() {
+        // A checkerboard only has parts of a single pixel
+        let (w, h) = (6, 5);
+        let checkerboard: Vec<u32> = (0..w * h).map(|i| ((i % w + i / w) % 2) as u32).collect();
+        for min_size in [0, 2, 40] {
+            assert_connected(&enforce_connectivity(&checkerboard, w, h, min_size), w);
+        }
+        // The same label in two places becomes two regions, unless they are small
+        let split = [0, 0, 1, 0, 0];
+        assert_eq!(enforce_connectivity(&split, 5, 1, 1), vec![0, 0, 1, 2, 2]);
+        assert_eq!(enforce_connectivity(&split, 5, 1, 3), vec![0, 0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,82 @@
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+/// A freehand line or polyline. The points are relative to the image size (0 to 1), like the center of round paths
+pub type Stroke = Vec<(f32, f32)>;
+
+/// Sorts along the strokes: Every stroke is filled with parallel lines, `width` pixels wide in total.
+/// Pixels covered by multiple strokes belong to the first one
+pub(crate) fn path_strokes(strokes: &[Stroke], w: u64, h: u64, width: u32) -> Vec<Vec<u64>> {
+    let radius = width.max(1) as f64 / 2.0;
+    strokes
+        .par_iter()
+        .filter(|stroke| !stroke.is_empty())
+        .flat_map_iter(|stroke| stroke_lines(stroke, w, h, radius))
+        .collect()
+}
+
+/// Returns the parallel lines of a single stroke
+fn stroke_lines(stroke: &Stroke, w: u64, h: u64, radius: f64) -> Vec<Vec<u64>> {
+    let points: Vec<(f64, f64)> = stroke.iter().map(|&(x, y)| (x as f64 * w as f64, y as f64 * h as f64)).collect();
+    // A single point is a segment without length
+    let segments: Vec<((f64, f64), (f64, f64))> = match points.len() {
+        1 => vec![(points[0], points[0])],
+        _ => points.windows(2).map(|p| (p[0], p[1])).collect(),
+    };
+
+    // The nearest segment of every pixel near the stroke: (distance, offset to the side, position along the stroke)
+    let mut nearest: HashMap<u64, (f64, f64, f64)> = HashMap::new();
+    let mut length = 0.0;
+    for (a, b) in segments {
+        let len = (b.0 - a.0).hypot(b.1 - a.1);
+        let (ux, uy) = if len > 0.0 { ((b.0 - a.0) / len, (b.1 - a.1) / len) } else { (1.0, 0.0) };
+        let (x0, x1) = ((a.0.min(b.0) - radius).floor().max(0.0) as u64, (a.0.max(b.0) + radius).ceil().clamp(0.0, w as f64) as u64);
+        let (y0, y1) = ((a.1.min(b.1) - radius).floor().max(0.0) as u64, (a.1.max(b.1) + radius).ceil().clamp(0.0, h as f64) as u64);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let (px, py) = (x as f64 + 0.5 - a.0, y as f64 + 0.5 - a.1);
+                let along = px * ux + py * uy;
+                let offset = ux * py - uy * px;
+                let closest = along.clamp(0.0, len);
+                let dist = (px - closest * ux).hypot(py - closest * uy);
+                if dist > radius {
+                    continue;
+                }
+                let entry = nearest.entry(y * w + x).or_insert((f64::MAX, 0.0, 0.0));
+                if dist < entry.0 {
+                    // Outside of the segment, the lines keep going straight, so the caps are filled too
+                    *entry = (dist, offset, length + along);
+                }
+            }
+        }
+        length += len;
+    }
+
+    let mut lines: Vec<(i64, f64, u64)> =
+        nearest.into_iter().map(|(i, (_, offset, along))| (offset.round() as i64, along, i)).collect();
+    lines.sort_unstable_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)).then(a.2.cmp(&b.2)));
+    lines.chunk_by(|a, b| a.0 == b.0).map(|l| l.iter().map(|&(_, _, i)| i).collect()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{path_creator::PathCreator, Pixelsorter};
+    use image::RgbImage;
+
+    #[test]
+    fn coverage_includes_the_strokes() {
+        let img = RgbImage::new(40, 20);
+        let mut ps = Pixelsorter::new();
+        ps.path_creator = PathCreator::Strokes { width: 40 };
+        assert_eq!(ps.path_creator.coverage(&img), None);
+        assert_eq!(ps.coverage(&img).uncovered, 40 * 20);
+
+        // A stroke through the middle that is wider than the image covers everything, once
+        ps.strokes = vec![vec![(0.0, 0.5), (1.0, 0.5)]];
+        let coverage = ps.coverage(&img);
+        assert_eq!((coverage.uncovered, coverage.duplicates, coverage.out_of_bounds), (0, 0, 0));
+
+        ps.path_creator = PathCreator::VerticalLines;
+        assert_eq!(ps.coverage(&img).uncovered, 0);
+    }
+}
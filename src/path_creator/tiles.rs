@@ -0,0 +1,134 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
+
+/// The direction of the lines inside of a tile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileDirection {
+    Right,
+    Left,
+    Down,
+    Up,
+}
+
+impl std::fmt::Display for TileDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TileDirection::Right => write!(f, "right"),
+            TileDirection::Left => write!(f, "left"),
+            TileDirection::Down => write!(f, "down"),
+            TileDirection::Up => write!(f, "up"),
+        }
+    }
+}
+
+const MAX_TILE_DIRECTIONS: usize = 8;
+
+/// A list of up to 8 directions. It has a fixed maximum length, so path creators can stay `Copy`
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct TileDirections {
+    list: [TileDirection; MAX_TILE_DIRECTIONS],
+    len: usize,
+}
+
+impl TileDirections {
+    /// Returns None if the list is empty or longer than 8 directions
+    pub fn new(directions: &[TileDirection]) -> Option<TileDirections> {
+        if directions.is_empty() || directions.len() > MAX_TILE_DIRECTIONS {
+            return None;
+        }
+        let mut list = [TileDirection::Right; MAX_TILE_DIRECTIONS];
+        list[..directions.len()].copy_from_slice(directions);
+        Some(TileDirections { list, len: directions.len() })
+    }
+
+    pub fn as_slice(&self) -> &[TileDirection] {
+        &self.list[..self.len]
+    }
+}
+
+impl std::fmt::Debug for TileDirections {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl std::fmt::Display for TileDirections {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<String> = self.as_slice().iter().map(|d| d.to_string()).collect();
+        write!(f, "{}", names.join("/"))
+    }
+}
+
+/// How the directions of the list are given to the tiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileOrder {
+    /// Goes through the list diagonally, so two directions make a checkerboard
+    Cycle,
+    /// Every tile picks a random direction of the list
+    Random { seed: u64 },
+}
+
+/// Cuts the image into square tiles of `size` pixels, each one sorted in its own direction
+pub(crate) fn path_tiles(w: u64, h: u64, size: u32, directions: TileDirections, order: TileOrder) -> Vec<Vec<u64>> {
+    let size = size.max(1) as u64;
+    let (gw, gh) = (w.div_ceil(size), h.div_ceil(size));
+    let list = directions.as_slice();
+    let tile_directions: Vec<TileDirection> = match order {
+        TileOrder::Cycle => (0..gw * gh).map(|t| list[((t % gw + t / gw) % list.len() as u64) as usize]).collect(),
+        TileOrder::Random { seed } => {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..gw * gh).map(|_| list[rng.gen_range(0..list.len())]).collect()
+        }
+    };
+
+    tile_directions
+        .into_par_iter()
+        .enumerate()
+        .flat_map_iter(|(t, direction)| {
+            let (x0, y0) = (t as u64 % gw * size, t as u64 / gw * size);
+            let (x1, y1) = ((x0 + size).min(w), (y0 + size).min(h));
+            let lines: Vec<Vec<u64>> = match direction {
+                TileDirection::Right => (y0..y1).map(|y| (x0..x1).map(|x| y * w + x).collect()).collect(),
+                TileDirection::Left => (y0..y1).map(|y| (x0..x1).rev().map(|x| y * w + x).collect()).collect(),
+                TileDirection::Down => (x0..x1).map(|x| (y0..y1).map(|y| y * w + x).collect()).collect(),
+                TileDirection::Up => (x0..x1).map(|x| (y0..y1).rev().map(|y| y * w + x).collect()).collect(),
+            };
+            lines
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path_creator::test_helpers::assert_covers_once;
+    use TileDirection::*;
+
+    #[test]
+    fn tiles_cover_every_pixel_once() {
+        let all = TileDirections::new(&[Right, Left, Down, Up]).unwrap();
+        // Sizes that divide the image, ones that leave partial tiles at the border and ones larger than the image
+        for (w, h) in [(1, 1), (12, 8), (13, 7)] {
+            for size in [0, 1, 4, 5, 20] {
+                for order in [TileOrder::Cycle, TileOrder::Random { seed: 3 }] {
+                    assert_covers_once(&path_tiles(w, h, size, all, order), w, h);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cycled_directions_make_a_checkerboard() {
+        let directions = TileDirections::new(&[Right, Down]).unwrap();
+        let paths = path_tiles(4, 4, 2, directions, TileOrder::Cycle);
+        // Top left goes right, top right goes down, bottom left goes down, bottom right goes right
+        assert_eq!(paths, vec![vec![0, 1], vec![4, 5], vec![2, 6], vec![3, 7], vec![8, 12], vec![9, 13], vec![10, 11], vec![14, 15]]);
+    }
+
+    #[test]
+    fn direction_lists_are_limited() {
+        assert!(TileDirections::new(&[]).is_none());
+        assert!(TileDirections::new(&[Up; MAX_TILE_DIRECTIONS + 1]).is_none());
+        assert_eq!(TileDirections::new(&[Left, Up]).unwrap().as_slice(), &[Left, Up]);
+    }
+}
@@ -0,0 +1,76 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// The eight neighbours of a pixel
+const STEPS: [(i64, i64); 8] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+
+/// Highest bias (in both directions) that is used
+const MAX_BIAS: f32 = 50.0;
+
+/// Self-avoiding random walks: A walk starts at the first pixel that isn't part of one yet
+/// and steps to random unvisited neighbours until there are none left.
+/// Steps towards the angle (in degrees, 0° goes to the right and 90° down) are preferred, more so with a higher `bias`
+pub(crate) fn path_random_walk(w: u64, h: u64, angle: f32, bias: f32, seed: u64) -> Vec<Vec<u64>> {
+    // Larger biases would make the weights of steps away from the angle round to 0 (or the others to infinity)
+    let bias = if bias.is_finite() { bias.clamp(-MAX_BIAS, MAX_BIAS) } else { 0.0 };
+    let (sin, cos) = (angle as f64).to_radians().sin_cos();
+    // Weight of every step, growing with how much it points in the bias direction
+    let weights = STEPS.map(|(dx, dy)| {
+        let alignment = (dx as f64 * cos + dy as f64 * sin) / (dx as f64).hypot(dy as f64);
+        (bias as f64 * alignment).exp()
+    });
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut visited = vec![false; (w * h) as usize];
+    let mut paths = Vec::new();
+    for start in 0..w * h {
+        if visited[start as usize] {
+            continue;
+        }
+        visited[start as usize] = true;
+        let mut path = vec![start];
+        let (mut x, mut y) = ((start % w) as i64, (start / w) as i64);
+        loop {
+            let mut options = [(0, 0.0); 8];
+            let mut count = 0;
+            let mut total = 0.0;
+            for (s, &(dx, dy)) in STEPS.iter().enumerate() {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx >= 0 && ny >= 0 && nx < w as i64 && ny < h as i64 && !visited[(ny as u64 * w + nx as u64) as usize] {
+                    total += weights[s];
+                    options[count] = (s, total);
+                    count += 1;
+                }
+            }
+            if count == 0 {
+                break;
+            }
+            // Pick a step, weighted. If the weights are unusable (f.e. a NaN angle), every step is as likely
+            let s = if total.is_finite() && total > 0.0 {
+                let pick = rng.gen_range(0.0..total);
+                options[..count].iter().find(|&&(_, sum)| pick < sum).map_or(options[count - 1].0, |&(s, _)| s)
+            } else {
+                options[rng.gen_range(0..count)].0
+            };
+            (x, y) = (x + STEPS[s].0, y + STEPS[s].1);
+            let i = y as u64 * w + x as u64;
+            visited[i as usize] = true;
+            path.push(i);
+        }
+        paths.push(path);
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path_creator::test_helpers::assert_covers_once;
+
+    #[test]
+    fn extreme_biases_dont_panic() {
+        for bias in [0.0, 1.0, 800.0, -800.0, f32::INFINITY, f32::NAN] {
+            assert_covers_once(&path_random_walk(30, 20, 90.0, bias, 7), 30, 20);
+        }
+        assert_covers_once(&path_random_walk(30, 20, f32::NAN, 1.0, 7), 30, 20);
+    }
+}
@@ -1,14 +1,14 @@
-use crate::color_helpers::*;
+use crate::{color_helpers::*, gradient, noise, PixelSpan};
 use image::Rgb;
 use rand::{
     distributions::{Distribution, Uniform},
-    thread_rng,
+    thread_rng, Rng,
 };
 use rayon::iter::{
     IndexedParallelIterator, IntoParallelIterator, IntoParallelRefMutIterator, ParallelDrainRange,
     ParallelIterator,
 };
-use std::{cmp::min, collections::VecDeque};
+use std::{cmp::min, collections::VecDeque, f64::consts::TAU};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PixelSelector {
@@ -16,14 +16,59 @@ pub enum PixelSelector {
     Fixed {
         len: u64,
     },
+    /// Spans with random lengths between min and max.
+    /// The variation (in percent) scales min and max randomly for every path
     Random {
+        min: u32,
         max: u32,
+        distribution: RandomDistribution,
+        variation: u32,
     },
     Threshold {
         min: u64,
         max: u64,
         criteria: PixelSelectCriteria,
     },
+    /// Starts a span only on a pixel inside [start_min, start_max] and continues it while pixels are inside [min, max].
+    /// Usually the continue range is wider than the start range, so spans don't flicker on and off in noisy regions
+    Hysteresis {
+        start_min: u64,
+        start_max: u64,
+        min: u64,
+        max: u64,
+        criteria: PixelSelectCriteria,
+    },
+    /// Ends a span wherever the edge strength (in the range [0, 255]) exceeds the threshold
+    Edges {
+        threshold: u64,
+    },
+    /// Continues spans while a smooth noise field at the position of the pixel is above the threshold (in the range [0, 255]).
+    /// The scale is the size of a noise cell in pixels
+    Noise {
+        kind: NoiseKind,
+        seed: u64,
+        scale: u32,
+        octaves: u32,
+        threshold: u64,
+    },
+}
+
+/// Type of noise a Noise-Selector uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseKind {
+    Perlin,
+    Simplex,
+    Worley,
+}
+
+/// Distribution the span lengths of a Random-Selector are drawn from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RandomDistribution {
+    Uniform,
+    Normal,
+    Exponential,
+    /// Smoothly changing lengths along the path
+    Perlin,
 }
 
 /// Key criteria which a (Threshold-)Selector should use as a key
@@ -35,54 +80,128 @@ pub enum PixelSelectCriteria {
 }
 
 impl PixelSelector {
-    /// Returns a list of pixel spans
+    /// Returns the function that extracts the value a selector compares against its thresholds
+    pub fn get_value_function(criteria: PixelSelectCriteria) -> for<'a> fn(&'a Rgb<u8>) -> f32 {
+        match criteria {
+            PixelSelectCriteria::Hue => get_hue_f32,
+            PixelSelectCriteria::Brightness => get_brightness_f32,
+            PixelSelectCriteria::Saturation => get_saturation_f32,
+        }
+    }
+
+    /// Computes values for every pixel of the whole image, for selectors that need more than the color of a single pixel.
+    /// The returned field is indexed the same way as the image (y * w + x)
+    pub fn create_field(self, pixels: &[&mut Rgb<u8>], w: u64, h: u64) -> Option<Vec<u16>> {
+        match self {
+            PixelSelector::Edges { .. } => Some(gradient::edge_strength(pixels, w, h)),
+            PixelSelector::Noise { kind, seed, scale, octaves, .. } => Some(noise_field(kind, seed, scale, octaves, w, h)),
+            _ => None,
+        }
+    }
+
+    /// Returns a list of pixel spans.
+    /// Selectors that need a field (see [PixelSelector::create_field]) select nothing if it is not given
     pub fn create_spans<'a>(
         self,
-        pixels: &mut VecDeque<&'a mut Rgb<u8>>,
-    ) -> Vec<Vec<&'a mut Rgb<u8>>> {
+        pixels: &mut VecDeque<(u64, &'a mut Rgb<u8>)>,
+        field: Option<&[u16]>,
+    ) -> Vec<PixelSpan<'a>> {
         match self {
             PixelSelector::Full => full_selector(pixels),
             PixelSelector::Fixed { len } => fixed_selector(pixels, len),
-            PixelSelector::Random { max } => random_selector(pixels, max),
+            PixelSelector::Random { min, max, distribution, variation } => {
+                random_selector(pixels, min, max, distribution, variation)
+            }
             PixelSelector::Threshold { min, max, criteria } => threshold_selector(pixels, criteria, min, max),
+            PixelSelector::Hysteresis { start_min, start_max, min, max, criteria } => {
+                hysteresis_selector(pixels, criteria, (start_min, start_max), (min, max))
+            }
+            PixelSelector::Edges { threshold } => match field {
+                // End spans at edges
+                Some(field) => field_selector(pixels, field, |edge| (edge as u64) <= threshold),
+                None => Vec::new(),
+            },
+            PixelSelector::Noise { threshold, .. } => match field {
+                Some(field) => field_selector(pixels, field, |noise| (noise as u64) >= threshold),
+                None => Vec::new(),
+            },
         }
     }
     pub fn info_string<'a>(self) -> String {
         match self {
             PixelSelector::Full => String::from("Selecing all pixels"),
             PixelSelector::Fixed { len } => format!("Selecting ranges of fixed length {}", len),
-            PixelSelector::Random { max } => format!("Random Selector with max length {}", max),
+            PixelSelector::Random { min, max, distribution, variation } => format!(
+                "Random Selector with length {} to {} ({:?} distribution, {}% variation per path)",
+                min, max, distribution, variation
+            ),
             PixelSelector::Threshold { min, max, criteria } => format!(
                 "Selecting Pixels with: [{} < {:?} < {}]",
                 min, criteria, max
             ),
+            PixelSelector::Hysteresis { start_min, start_max, min, max, criteria } => format!(
+                "Selecting Pixels with: Start [{} < {:?} < {}], Continue [{} < {:?} < {}]",
+                start_min, criteria, start_max, min, criteria, max
+            ),
+            PixelSelector::Edges { threshold } => format!("Selecting Pixels between edges stronger than {}", threshold),
+            PixelSelector::Noise { kind, seed, scale, octaves, threshold } => format!(
+                "Selecting Pixels where {:?} noise (seed {}, scale {}, {} octaves) is above {}",
+                kind, seed, scale, octaves, threshold
+            ),
         }
     }
-    pub fn mask(self, pixels: &mut Vec<&mut Rgb<u8>>) -> Result<(), ()> {
-        if let PixelSelector::Threshold { min, max, criteria } = self {
-            let value_function = match criteria {
-                PixelSelectCriteria::Hue => get_hue,
-                PixelSelectCriteria::Brightness => get_brightness,
-                PixelSelectCriteria::Saturation => get_saturation,
-            };
-            let valid = |val| (val as u64) >= min && (val as u64) <= max;
-            pixels.iter_mut().for_each(|p| {
-                if valid(value_function(p)) {
-                    **p = Rgb { 0: [255, 255, 255] };
-                } else {
-                    **p = Rgb { 0: [0, 0, 0] };
-                }
-            });
-            return Ok(());
-        }
-        Err(())
+    /// Paints all pixels that would be selected white and all others black.
+    /// For the Hysteresis selector, pixels that can only continue a span are painted gray.
+    /// Only works for the Threshold, Hysteresis, Edges and Noise selector
+    pub fn mask(self, pixels: &mut Vec<&mut Rgb<u8>>, w: u64, h: u64) -> Result<(), ()> {
+        let in_range = |val: f32, (min, max): (u64, u64)| in_range(val, min, max);
+        let mask_values: Vec<u8> = match self {
+            PixelSelector::Threshold { min, max, criteria } => {
+                let value_function = PixelSelector::get_value_function(criteria);
+                pixels
+                    .iter()
+                    .map(|p| if in_range(value_function(p), (min, max)) { 255 } else { 0 })
+                    .collect()
+            }
+            PixelSelector::Hysteresis { start_min, start_max, min, max, criteria } => {
+                let value_function = PixelSelector::get_value_function(criteria);
+                pixels
+                    .iter()
+                    .map(|p| {
+                        let val = value_function(p);
+                        if in_range(val, (start_min, start_max)) {
+                            255
+                        } else if in_range(val, (min, max)) {
+                            127
+                        } else {
+                            0
+                        }
+                    })
+                    .collect()
+            }
+            PixelSelector::Edges { threshold } => gradient::edge_strength(pixels, w, h)
+                .into_iter()
+                .map(|e| if (e as u64) <= threshold { 255 } else { 0 })
+                .collect(),
+            PixelSelector::Noise { threshold, .. } => self
+                .create_field(pixels, w, h)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|n| if (n as u64) >= threshold { 255 } else { 0 })
+                .collect(),
+            _ => return Err(()),
+        };
+        pixels.iter_mut().zip(mask_values).for_each(|(p, v)| {
+            **p = Rgb { 0: [v, v, v] };
+        });
+        Ok(())
     }
 }
 
-fn full_selector<'a>(pixels: &mut VecDeque<&'a mut Rgb<u8>>) -> Vec<Vec<&'a mut Rgb<u8>>> {
-    let mut spans: Vec<Vec<&'a mut Rgb<u8>>> = Vec::new();
+fn full_selector<'a>(pixels: &mut VecDeque<(u64, &'a mut Rgb<u8>)>) -> Vec<PixelSpan<'a>> {
+    let mut spans: Vec<PixelSpan<'a>> = Vec::new();
 
-    let mut span: Vec<&mut Rgb<u8>> = Vec::new();
+    let mut span: PixelSpan<'_> = Vec::new();
     while !pixels.is_empty() {
         span.push(pixels.pop_front().unwrap());
     }
@@ -91,10 +210,10 @@ fn full_selector<'a>(pixels: &mut VecDeque<&'a mut Rgb<u8>>) -> Vec<Vec<&'a mut
 }
 
 fn fixed_selector<'a>(
-    pixels: &mut VecDeque<&'a mut Rgb<u8>>,
+    pixels: &mut VecDeque<(u64, &'a mut Rgb<u8>)>,
     len: u64,
-) -> Vec<Vec<&'a mut Rgb<u8>>> {
-    let mut spans: Vec<Vec<&'a mut Rgb<u8>>> = Vec::new();
+) -> Vec<PixelSpan<'a>> {
+    let mut spans: Vec<PixelSpan<'a>> = Vec::new();
 
     // Prevent an endless loop
     if len == 0 {
@@ -112,51 +231,66 @@ fn fixed_selector<'a>(
 }
 
 fn random_selector<'a>(
-    pixels: &mut VecDeque<&'a mut Rgb<u8>>,
+    pixels: &mut VecDeque<(u64, &'a mut Rgb<u8>)>,
+    min: u32,
     max: u32,
-) -> Vec<Vec<&'a mut Rgb<u8>>> {
-    let mut spans: Vec<Vec<&'a mut Rgb<u8>>> = Vec::new();
-    // rng_range cannot be 1..1
-    if max <= 1 {
-        return spans;
-    }
+    distribution: RandomDistribution,
+    variation: u32,
+) -> Vec<PixelSpan<'a>> {
+    let mut spans: Vec<PixelSpan<'a>> = Vec::new();
     let mut rng = thread_rng();
-    let rng_range = Uniform::from(1..max as usize);
 
-    loop {
-        let mut r = rng_range.sample(&mut rng);
-        if pixels.len() < r {
-            break;
-        }
+    // Scale the lengths of this path by a random factor, so every path looks a bit different
+    let factor = 1.0 + rng.gen_range(-1.0..=1.0) * variation.min(100) as f64 / 100.0;
+    let min = (min.max(1) as f64 * factor).max(1.0);
+    let max = (max as f64 * factor).max(min);
+    let noise_seed: u64 = rng.gen();
+
+    let mut position = 0;
+    while !pixels.is_empty() {
+        let r = match distribution {
+            RandomDistribution::Uniform => rng.gen_range(min..=max),
+            RandomDistribution::Normal => {
+                // Box-Muller transform, with 99.7% of the values between min and max
+                let (u1, u2): (f64, f64) = (rng.gen_range(f64::EPSILON..1.0), rng.gen());
+                let z = (-2.0 * u1.ln()).sqrt() * (TAU * u2).cos();
+                (min + max) / 2.0 + z * (max - min) / 6.0
+            }
+            RandomDistribution::Exponential => min - (1.0 - rng.gen::<f64>()).ln() * (max - min) / 4.0,
+            // The length changes smoothly along the path
+            RandomDistribution::Perlin => min + noise::perlin_1d(position as f64 / max, noise_seed) * (max - min),
+        };
+        let r = (r.round().clamp(min.round(), max.round()) as usize).min(pixels.len());
         // Take r pixels and put into new span
         spans.push(pixels.drain(0..r).collect());
+        position += r;
     }
-    // Push the rest
-    spans.push(pixels.drain(..).collect());
 
     spans
 }
 
+/// Checks if a value lies in the integer range. `max` includes everything that rounds down to it (f.e. 80.5 for 80),
+/// so the float values select the same pixels as the integer ones and the histograms of the analysis
+fn in_range(val: f32, min: u64, max: u64) -> bool {
+    val >= min as f32 && val < (max + 1) as f32
+}
+
 fn threshold_selector<'a>(
-    pixels: &mut VecDeque<&'a mut Rgb<u8>>,
+    pixels: &mut VecDeque<(u64, &'a mut Rgb<u8>)>,
     criteria: PixelSelectCriteria,
     min: u64,
     max: u64,
-) -> Vec<Vec<&'a mut Rgb<u8>>> {
-    let mut spans: Vec<Vec<&'a mut Rgb<u8>>> = Vec::new();
+) -> Vec<PixelSpan<'a>> {
+    let mut spans: Vec<PixelSpan<'a>> = Vec::new();
 
-    let value_function = match criteria {
-        PixelSelectCriteria::Hue => get_hue,
-        PixelSelectCriteria::Brightness => get_brightness,
-        PixelSelectCriteria::Saturation => get_saturation,
-    };
+    let value_function = PixelSelector::get_value_function(criteria);
 
     // Function that checks if a value is valid
-    let valid = |val| (val as u64) >= min && (val as u64) <= max;
+    let valid = |val: f32| in_range(val, min, max);
 
-    let mut span: Vec<&mut Rgb<u8>> = Vec::new();
+    let mut span: PixelSpan<'_> = Vec::new();
     for _ in 0..pixels.len() {
-        let value = value_function(pixels.get(0).unwrap());
+        let value = value_function(pixels.get(0).unwrap().1);
         let px = pixels.pop_front().unwrap();
 
         if valid(value) {
@@ -175,3 +309,116 @@ fn threshold_selector<'a>(
     spans
 }
 
+
+fn hysteresis_selector<'a>(
+    pixels: &mut VecDeque<(u64, &'a mut Rgb<u8>)>,
+    criteria: PixelSelectCriteria,
+    (start_min, start_max): (u64, u64),
+    (min, max): (u64, u64),
+) -> Vec<PixelSpan<'a>> {
+    let mut spans: Vec<PixelSpan<'a>> = Vec::new();
+
+    let value_function = PixelSelector::get_value_function(criteria);
+    let can_start = |val: f32| in_range(val, start_min, start_max);
+    // A pixel that could start a span can always continue it
+    let can_continue = |val: f32| in_range(val, min, max) || can_start(val);
+
+    let mut span: PixelSpan<'_> = Vec::new();
+    while let Some(px) = pixels.pop_front() {
+        let value = value_function(px.1);
+
+        if span.is_empty() {
+            // Only start a span on a pixel in the strict range
+            if can_start(value) {
+                span.push(px);
+            }
+        } else if can_continue(value) {
+            span.push(px);
+        } else {
+            spans.push(span);
+            span = Vec::new();
+        }
+    }
+    spans.push(span);
+    spans
+}
+
+/// Returns the noise value of every pixel in the range [0, 255]
+fn noise_field(kind: NoiseKind, seed: u64, scale: u32, octaves: u32, w: u64, h: u64) -> Vec<u16> {
+    let noise_function = match kind {
+        NoiseKind::Perlin => noise::perlin_2d,
+        NoiseKind::Simplex => noise::simplex_2d,
+        NoiseKind::Worley => noise::worley_2d,
+    };
+    let scale = scale.max(1) as f64;
+    (0..w * h)
+        .into_par_iter()
+        .map(|i| {
+            let (x, y) = ((i % w) as f64 / scale, (i / w) as f64 / scale);
+            (noise::fractal_2d(noise_function, x, y, octaves, seed) * 255.0) as u16
+        })
+        .collect()
+}
+
+/// Selects spans of pixels whose value in the field is valid
+fn field_selector<'a>(
+    pixels: &mut VecDeque<(u64, &'a mut Rgb<u8>)>,
+    field: &[u16],
+    valid: impl Fn(u16) -> bool,
+) -> Vec<PixelSpan<'a>> {
+    let mut spans: Vec<PixelSpan<'a>> = Vec::new();
+
+    let mut span: PixelSpan<'_> = Vec::new();
+    while let Some(px) = pixels.pop_front() {
+        let value = field.get(px.0 as usize).copied().unwrap_or(0);
+
+        if valid(value) {
+            span.push(px);
+        } else {
+            // Close the span (and leave the invalid pixel as it is)
+            if !span.is_empty() {
+                spans.push(span);
+                span = Vec::new();
+            }
+        }
+    }
+    if !span.is_empty() {
+        spans.push(span);
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_spans_are_never_empty() {
+        let mut colors = [Rgb([0, 0, 0]); 6];
+        let field = [1, 1, 0, 1, 0, 0];
+        let mut pixels: VecDeque<(u64, &mut Rgb<u8>)> = colors.iter_mut().enumerate().map(|(i, px)| (i as u64, px)).collect();
+        let spans = field_selector(&mut pixels, &field, |v| v > 0);
+        let indices: Vec<Vec<u64>> = spans.iter().map(|span| span.iter().map(|(i, _)| *i).collect()).collect();
+        assert_eq!(indices, vec![vec![0, 1], vec![3]]);
+    }
+
+    #[test]
+    fn float_thresholds_match_integer_values() {
+        type IntegerFunction = fn(&Rgb<u8>) -> u16;
+        let integer_functions: [(PixelSelectCriteria, IntegerFunction); 3] = [
+            (PixelSelectCriteria::Hue, get_hue),
+            (PixelSelectCriteria::Brightness, get_brightness),
+            (PixelSelectCriteria::Saturation, get_saturation),
+        ];
+        for (criteria, integer_function) in integer_functions {
+            let value_function = PixelSelector::get_value_function(criteria);
+            for c in (0..=255u8).step_by(5) {
+                let px = Rgb([c, c.wrapping_mul(7), 255 - c]);
+                for (min, max) in [(0, 80), (80, 80), (81, 200), (100, 360)] {
+                    let integer = integer_function(&px) as u64;
+                    assert_eq!(in_range(value_function(&px), min, max), integer >= min && integer <= max);
+                }
+            }
+        }
+    }
+}
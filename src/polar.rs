@@ -0,0 +1,149 @@
+use image::{Rgb, RgbImage};
+use rayon::prelude::*;
+use std::f64::consts::TAU;
+
+use crate::{path_creator::RoundOptions, rotate::sample, PixelSpan};
+
+/// Unwraps an image into polar coordinates and wraps the result back.
+/// In the unwrapped image, every column is an angle and every row a radius, the first row is the center.
+/// So rows become circles and columns become rays
+pub struct Polar {
+    w: u64,
+    h: u64,
+    /// Number of angles
+    pw: u64,
+    /// Number of radii
+    ph: u64,
+    center: (f64, f64),
+    radius_scale: (f64, f64),
+    start_angle: f64,
+}
+
+impl Polar {
+    pub fn new(w: u64, h: u64, options: RoundOptions) -> Polar {
+        let center = options.center_px(w, h);
+        let radius_scale = options.radius_scale();
+        // Reach the farthest corner
+        let max_radius = [(0.0, 0.0), (w as f64, 0.0), (0.0, h as f64), (w as f64, h as f64)]
+            .iter()
+            .map(|&(x, y)| ((x - center.0) / radius_scale.0).hypot((y - center.1) / radius_scale.1))
+            .fold(1.0, f64::max);
+        Polar {
+            w,
+            h,
+            // The outermost circle gets about one sample per pixel
+            pw: (TAU * max_radius).ceil() as u64,
+            ph: max_radius.ceil() as u64,
+            center,
+            radius_scale,
+            start_angle: (options.start_angle as f64).to_radians(),
+        }
+    }
+
+    /// Size of the unwrapped image
+    pub fn unwrapped_size(&self) -> (u64, u64) {
+        (self.pw, self.ph)
+    }
+
+    /// Position in the original image of a pixel in the unwrapped image
+    fn to_original(&self, a: u64, r: u64) -> (f64, f64) {
+        let angle = self.start_angle + (a as f64 + 0.5) / self.pw as f64 * TAU;
+        let radius = r as f64 + 0.5;
+        (
+            self.center.0 + radius * angle.cos() * self.radius_scale.0,
+            self.center.1 + radius * angle.sin() * self.radius_scale.1,
+        )
+    }
+
+    /// Returns the unwrapped image and which of its pixels lie inside of the original image
+    pub fn unwrap(&self, pixels: &[&mut Rgb<u8>]) -> (RgbImage, Vec<bool>) {
+        let get = |x: u64, y: u64| *pixels[(y * self.w + x) as usize];
+        let (unwrapped, inside): (Vec<[u8; 3]>, Vec<bool>) = (0..self.pw * self.ph)
+            .into_par_iter()
+            .map(|i| {
+                let (x, y) = self.to_original(i % self.pw, i / self.pw);
+                let inside = x >= 0.0 && y >= 0.0 && x < self.w as f64 && y < self.h as f64;
+                (sample(get, self.w, self.h, x - 0.5, y - 0.5).0, inside)
+            })
+            .unzip();
+        let unwrapped = RgbImage::from_raw(self.pw as u32, self.ph as u32, unwrapped.concat()).unwrap();
+        (unwrapped, inside)
+    }
+
+    /// Writes the unwrapped image back into the original pixels.
+    /// Only pixels that are sampled from a `changed` pixel of the unwrapped image are written,
+    /// so the parts that didn't get sorted stay as sharp as they were
+    pub fn wrap_back(&self, unwrapped: &RgbImage, changed: &[bool], pixels: Vec<&mut Rgb<u8>>) {
+        let get = |x: u64, y: u64| *unwrapped.get_pixel(x as u32, y as u32);
+        pixels.into_par_iter().enumerate().for_each(|(i, px)| {
+            let dx = ((i as u64 % self.w) as f64 + 0.5 - self.center.0) / self.radius_scale.0;
+            let dy = ((i as u64 / self.w) as f64 + 0.5 - self.center.1) / self.radius_scale.1;
+            let angle = (dy.atan2(dx) - self.start_angle).rem_euclid(TAU);
+            let a = angle / TAU * self.pw as f64 - 0.5;
+            let r = dx.hypot(dy) - 0.5;
+
+            // The angles wrap around, so interpolate between the last and the first column
+            let a0 = a.floor();
+            let t = a - a0;
+            let (a0, a1) = ((a0 as i64).rem_euclid(self.pw as i64) as u64, ((a0 as i64 + 1).rem_euclid(self.pw as i64)) as u64);
+            let r0 = r.clamp(0.0, (self.ph - 1) as f64).floor() as u64;
+            let r1 = (r0 + 1).min(self.ph - 1);
+            let is_changed = |a: u64, r: u64| changed[(r * self.pw + a) as usize];
+            if !(is_changed(a0, r0) || is_changed(a1, r0) || is_changed(a0, r1) || is_changed(a1, r1)) {
+                return;
+            }
+            let (left, right) = (sample(get, self.pw, self.ph, a0 as f64, r), sample(get, self.pw, self.ph, a1 as f64, r));
+            *px = Rgb(std::array::from_fn(|ch| (left.0[ch] as f64 * (1.0 - t) + right.0[ch] as f64 * t).round() as u8));
+        });
+    }
+}
+
+/// Cuts the paths where they leave the original image, so only pixels inside of it get sorted
+pub fn cut_outside<'a>(ranges: Vec<PixelSpan<'a>>, inside: &[bool]) -> Vec<PixelSpan<'a>> {
+    let mut cut = Vec::new();
+    for range in ranges {
+        let mut part = Vec::new();
+        for (i, px) in range {
+            if inside[i as usize] {
+                part.push((i, px));
+            } else if !part.is_empty() {
+                cut.push(std::mem::take(&mut part));
+            }
+        }
+        if !part.is_empty() {
+            cut.push(part);
+        }
+    }
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{path_creator::PathCreator, rotate::test_helpers::assert_unselected_pixels_stay_sharp, Pixelsorter};
+    use image::{Rgb, RgbImage};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn unselected_pixels_stay_sharp() {
+        let mut ps = Pixelsorter::new();
+        ps.path_creator = PathCreator::VerticalLines;
+        ps.polar = Some(Default::default());
+        assert_unselected_pixels_stay_sharp(ps);
+    }
+
+    #[test]
+    fn rotated_rows_stay_inside() {
+        // Without a rotation, the rotated rows are the rows, so both have to be cut the same way
+        let mut rng = StdRng::seed_from_u64(7);
+        let original = RgbImage::from_fn(50, 30, |_, _| Rgb(rng.gen()));
+        let sorted = |path_creator| {
+            let mut ps = Pixelsorter::new();
+            ps.path_creator = path_creator;
+            ps.polar = Some(Default::default());
+            let mut img = original.clone();
+            ps.sort(&mut img);
+            img
+        };
+        assert_eq!(sorted(PathCreator::Rotated(0.0)), sorted(PathCreator::HorizontalLines));
+    }
+}
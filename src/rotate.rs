@@ -0,0 +1,196 @@
+use image::{Rgb, RgbImage};
+use rayon::prelude::*;
+
+/// Rotates an image so lines at an angle become rows, and rotates the result back.
+/// The angle is in degrees, 0° goes to the right and 90° goes down
+pub struct Rotation {
+    w: u64,
+    h: u64,
+    rw: u64,
+    rh: u64,
+    /// Direction along the rows of the rotated image
+    dir: (f64, f64),
+    /// Direction along the columns of the rotated image
+    normal: (f64, f64),
+}
+
+/// Returns the pixels (x0, y0, x1, y1) between which the image is sampled at a position, clamped to the border
+fn neighbours(w: u64, h: u64, x: f64, y: f64) -> (f64, f64, u64, u64, u64, u64) {
+    let x = x.clamp(0.0, (w - 1) as f64);
+    let y = y.clamp(0.0, (h - 1) as f64);
+    let (x0, y0) = (x.floor() as u64, y.floor() as u64);
+    (x, y, x0, y0, (x0 + 1).min(w - 1), (y0 + 1).min(h - 1))
+}
+
+/// Samples the image at a position between pixel centers (bilinear), positions outside are clamped to the border.
+/// An empty image has nothing to sample, so it is black
+pub(crate) fn sample(get: impl Fn(u64, u64) -> Rgb<u8>, w: u64, h: u64, x: f64, y: f64) -> Rgb<u8> {
+    if w == 0 || h == 0 {
+        return Rgb([0, 0, 0]);
+    }
+    let (x, y, x0, y0, x1, y1) = neighbours(w, h, x, y);
+    let (tx, ty) = (x - x0 as f64, y - y0 as f64);
+
+    let (a, b, c, d) = (get(x0, y0), get(x1, y0), get(x0, y1), get(x1, y1));
+    Rgb(std::array::from_fn(|ch| {
+        let top = a.0[ch] as f64 * (1.0 - tx) + b.0[ch] as f64 * tx;
+        let bottom = c.0[ch] as f64 * (1.0 - tx) + d.0[ch] as f64 * tx;
+        (top * (1.0 - ty) + bottom * ty).round() as u8
+    }))
+}
+
+impl Rotation {
+    pub fn new(w: u64, h: u64, angle: f32) -> Rotation {
+        let (sin, cos) = (angle as f64).to_radians().sin_cos();
+        let rw = (w as f64 * cos.abs() + h as f64 * sin.abs()).ceil().max(1.0) as u64;
+        let rh = (w as f64 * sin.abs() + h as f64 * cos.abs()).ceil().max(1.0) as u64;
+        Rotation { w, h, rw, rh, dir: (cos, sin), normal: (-sin, cos) }
+    }
+
+    /// Size of the rotated image, big enough to contain the whole original image
+    pub fn rotated_size(&self) -> (u64, u64) {
+        (self.rw, self.rh)
+    }
+
+    /// Position in the original image of a pixel in the rotated image
+    fn to_original(&self, x: u64, y: u64) -> (f64, f64) {
+        let u = x as f64 + 0.5 - self.rw as f64 / 2.0;
+        let v = y as f64 + 0.5 - self.rh as f64 / 2.0;
+        (
+            self.w as f64 / 2.0 + u * self.dir.0 + v * self.normal.0,
+            self.h as f64 / 2.0 + u * self.dir.1 + v * self.normal.1,
+        )
+    }
+
+    /// Position in the rotated image of a pixel in the original image
+    fn to_rotated(&self, x: u64, y: u64) -> (f64, f64) {
+        let dx = x as f64 + 0.5 - self.w as f64 / 2.0;
+        let dy = y as f64 + 0.5 - self.h as f64 / 2.0;
+        (
+            dx * self.dir.0 + dy * self.dir.1 + self.rw as f64 / 2.0 - 0.5,
+            dx * self.normal.0 + dy * self.normal.1 + self.rh as f64 / 2.0 - 0.5,
+        )
+    }
+
+    /// Returns the rotated image and the rows of it that lie inside of the original image, as indices.
+    /// The corners outside of the original image are filled with the nearest border pixels
+    pub fn rotate(&self, pixels: &[&mut Rgb<u8>]) -> (RgbImage, Vec<Vec<u64>>) {
+        let get = |x: u64, y: u64| *pixels[(y * self.w + x) as usize];
+        let rotated: Vec<u8> = (0..self.rw * self.rh)
+            .into_par_iter()
+            .flat_map_iter(|i| {
+                let (x, y) = self.to_original(i % self.rw, i / self.rw);
+                sample(get, self.w, self.h, x - 0.5, y - 0.5).0
+            })
+            .collect();
+        let rotated = RgbImage::from_raw(self.rw as u32, self.rh as u32, rotated).unwrap();
+
+        // The original image is convex, so every row only has one part inside of it
+        let rows = (0..self.rh)
+            .into_par_iter()
+            .map(|y| {
+                (0..self.rw)
+                    .filter(|&x| {
+                        let (ox, oy) = self.to_original(x, y);
+                        ox >= 0.0 && oy >= 0.0 && ox < self.w as f64 && oy < self.h as f64
+                    })
+                    .map(|x| y * self.rw + x)
+                    .collect()
+            })
+            .collect();
+        (rotated, rows)
+    }
+
+    /// Rotates a mask of the original image, f.e. which pixels are inside of the unwrapped polar image.
+    /// A pixel of the rotated image is only set if all the pixels it is sampled from are set
+    pub fn rotate_mask(&self, mask: &[bool]) -> Vec<bool> {
+        if self.w == 0 || self.h == 0 {
+            return vec![false; (self.rw * self.rh) as usize];
+        }
+        (0..self.rw * self.rh)
+            .into_par_iter()
+            .map(|i| {
+                let (x, y) = self.to_original(i % self.rw, i / self.rw);
+                let (x, y, x0, y0, x1, y1) = neighbours(self.w, self.h, x - 0.5, y - 0.5);
+                // Neighbours that don't get any weight don't matter
+                let (x1, y1) = (if x > x0 as f64 { x1 } else { x0 }, if y > y0 as f64 { y1 } else { y0 });
+                let is_set = |x: u64, y: u64| mask[(y * self.w + x) as usize];
+                is_set(x0, y0) && is_set(x1, y0) && is_set(x0, y1) && is_set(x1, y1)
+            })
+            .collect()
+    }
+
+    /// Writes the rotated image back into the original pixels.
+    /// Only pixels that are sampled from a `changed` pixel of the rotated image are written,
+    /// so the parts that didn't get sorted stay as sharp as they were
+    pub fn rotate_back(&self, rotated: &RgbImage, changed: &[bool], pixels: Vec<&mut Rgb<u8>>) {
+        let get = |x: u64, y: u64| *rotated.get_pixel(x as u32, y as u32);
+        pixels.into_par_iter().enumerate().for_each(|(i, px)| {
+            let (x, y) = self.to_rotated(i as u64 % self.w, i as u64 / self.w);
+            let (_, _, x0, y0, x1, y1) = neighbours(self.rw, self.rh, x, y);
+            let is_changed = |x: u64, y: u64| changed[(y * self.rw + x) as usize];
+            if is_changed(x0, y0) || is_changed(x1, y0) || is_changed(x0, y1) || is_changed(x1, y1) {
+                *px = sample(get, self.rw, self.rh, x, y);
+            }
+        });
+    }
+}
+
+/// Returns which pixels differ between the two images, f.e. before and after sorting
+pub(crate) fn changed_pixels(before: &RgbImage, after: &RgbImage) -> Vec<bool> {
+    before.pixels().zip(after.pixels()).map(|(a, b)| a != b).collect()
+}
+
+/// Helpers for the tests of the sorts that transform the image (rotating and polar coordinates)
+#[cfg(test)]
+pub(crate) mod test_helpers {
+    use crate::{pixel_selector::{PixelSelectCriteria, PixelSelector}, Pixelsorter};
+    use image::{Rgb, RgbImage};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// Asserts that the sorter only changes selected pixels and the ones right next to them.
+    /// It is tested on noise in the left half, the right half is dark enough to never be selected
+    pub(crate) fn assert_unselected_pixels_stay_sharp(mut ps: Pixelsorter) {
+        let mut rng = StdRng::seed_from_u64(1);
+        let original = RgbImage::from_fn(60, 40, |x, _| {
+            if x < 30 { Rgb(rng.gen::<[u8; 3]>().map(|c| c / 2 + 128)) } else { Rgb(rng.gen::<[u8; 3]>().map(|c| c / 4)) }
+        });
+        let criteria = PixelSelectCriteria::Brightness;
+
+        // Nothing is selected, nothing changes
+        let mut img = original.clone();
+        ps.selector = PixelSelector::Threshold { min: 256, max: 300, criteria };
+        ps.sort(&mut img);
+        assert_eq!(img, original);
+
+        // Only the bright half is sorted, pixels a bit away from it are untouched
+        let mut img = original.clone();
+        ps.selector = PixelSelector::Threshold { min: 100, max: 255, criteria };
+        ps.sort(&mut img);
+        assert_ne!(img, original);
+        for (x, y, px) in img.enumerate_pixels().filter(|(x, _, _)| *x > 33) {
+            assert_eq!(px, original.get_pixel(x, y));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{test_helpers::assert_unselected_pixels_stay_sharp, *};
+    use crate::{path_creator::PathCreator, Pixelsorter};
+
+    #[test]
+    fn unselected_pixels_stay_sharp() {
+        let mut ps = Pixelsorter::new();
+        ps.path_creator = PathCreator::Rotated(30.0);
+        assert_unselected_pixels_stay_sharp(ps);
+    }
+
+    #[test]
+    fn empty_images() {
+        assert_eq!(sample(|_, _| Rgb([1, 2, 3]), 0, 0, 0.0, 0.0), Rgb([0, 0, 0]));
+        let mut ps = Pixelsorter::new();
+        ps.path_creator = PathCreator::Rotated(30.0);
+        ps.sort(&mut RgbImage::new(0, 0));
+    }
+}
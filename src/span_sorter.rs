@@ -1,15 +1,44 @@
-use image::Rgb;
+use image::{imageops, Rgb, RgbImage};
+use std::sync::Arc;
 
 use crate::color_helpers;
+mod effects;
 mod glitchsort;
 mod mapsort;
 mod random_color;
 mod shellsort;
+mod stablesort;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct SpanSorter {
     pub criteria: SortingCriteria,
     pub algorithm: SortingAlgorithm,
+    pub key_source: KeySource,
+    /// Round the keys down to integers, like before there were float keys.
+    /// Pixels with almost the same color then get the same key
+    pub quantize: bool,
+}
+
+/// Where the values we sort by are read from
+#[derive(Debug, Clone, Default)]
+pub enum KeySource {
+    /// The sorted pixels themselves
+    #[default]
+    Pixels,
+    /// The pixels of a guide image (f.e. a depth map) at the same position.
+    /// It gets stretched to the size of the sorted image
+    Image(Arc<RgbImage>),
+}
+
+impl PartialEq for KeySource {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (KeySource::Pixels, KeySource::Pixels) => true,
+            // Only compare the pixels if they are different images, f.e. when the same file was opened again
+            (KeySource::Image(a), KeySource::Image(b)) => Arc::ptr_eq(a, b) || a == b,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -24,7 +53,65 @@ pub enum SortingAlgorithm {
     Mapsort,
     Shellsort,
     Glitchsort,
-    DebugColor,
+    /// Stable comparison sort, equal keys are ordered by the tie policy
+    Stablesort(TiePolicy),
+    /// Paints every span in one color, to see where they are
+    DebugColor(DebugColoring),
+    // Effects that don't sort, but change the spans in other ways
+    /// Reverses the order of the pixels
+    Reverse,
+    /// Puts the pixels in a random order
+    Shuffle,
+    /// Fills the span with its first pixel
+    Smear,
+    /// Fills the span with its average color
+    Average,
+    /// Fills the span with a gradient between its first and last pixel
+    Gradient,
+    /// Shifts the pixels cyclically by the given amount
+    Rotate(i32),
+}
+
+/// How the color of a span is picked when debugging
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DebugColoring {
+    /// A random color seeded by the index of the first pixel
+    #[default]
+    Start,
+    /// From blue for short spans to red for long spans
+    Length,
+    /// A random color seeded by the position of the span on its path
+    Index,
+}
+
+/// The order of pixels with equal keys after a stable sort
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TiePolicy {
+    /// The order they had before
+    #[default]
+    Keep,
+    /// The reversed order they had before
+    Reverse,
+    /// A random order, seeded by the position and the keys of the span so it stays the same between sorts
+    Random,
+}
+
+/// Where a span lies in the image
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SpanInfo {
+    /// Index of the first pixel of the span
+    pub start: u64,
+    /// Position of the span on its path, counting from 0
+    pub index: usize,
+}
+
+impl SpanInfo {
+    /// Seed for the random orders. Mixes the position of the span with its keys (FNV-1a like),
+    /// so spans sorted without a position (see [SpanSorter::sort]) still get different orders
+    pub(crate) fn seed(self, keys: &[f32]) -> u64 {
+        let start = self.start ^ (self.index as u64).rotate_left(32);
+        keys.iter().fold(start ^ 0xcbf29ce484222325, |hash, key| (hash ^ key.to_bits() as u64).wrapping_mul(0x100000001b3))
+    }
 }
 
 impl SpanSorter {
@@ -33,10 +120,18 @@ impl SpanSorter {
         SpanSorter {
             criteria,
             algorithm: SortingAlgorithm::Mapsort,
+            key_source: KeySource::Pixels,
+            quantize: false,
         }
     }
     pub fn info_string(&self) -> String {
-        format!("Sorting pixel intervals by [{:?}] using [{:?}]", self.criteria, self.algorithm)
+        format!(
+            "Sorting pixel intervals by [{:?}{}{}] using [{:?}]",
+            self.criteria,
+            if let KeySource::Image(_) = self.key_source { " of the guide image" } else { "" },
+            if self.quantize { ", rounded to integers" } else { "" },
+            self.algorithm
+        )
     }
 
     // Set criteria of SpanSorter
@@ -44,14 +139,17 @@ impl SpanSorter {
         self.criteria = criteria;
     }
 
-    /// Returns the function that extracts the value we sort by.
+    /// Returns the function that extracts the value we sort by, rounded down to an integer if `quantize` is set.
     ///
     /// For example when sorting by Hue, this returns the function that calculates the hue of a pixel.
-    pub fn get_value_function(criteria: SortingCriteria) -> for<'a> fn(&'a Rgb<u8>) -> u16 {
-        match criteria {
-            SortingCriteria::Brightness => color_helpers::get_brightness,
-            SortingCriteria::Saturation => color_helpers::get_saturation,
-            SortingCriteria::Hue | _ => color_helpers::get_hue,
+    pub fn get_value_function(criteria: SortingCriteria, quantize: bool) -> for<'a> fn(&'a Rgb<u8>) -> f32 {
+        match (criteria, quantize) {
+            (SortingCriteria::Brightness, false) => color_helpers::get_brightness_f32,
+            (SortingCriteria::Brightness, true) => |p| color_helpers::get_brightness(p) as f32,
+            (SortingCriteria::Saturation, false) => color_helpers::get_saturation_f32,
+            (SortingCriteria::Saturation, true) => |p| color_helpers::get_saturation(p) as f32,
+            (SortingCriteria::Hue, false) => color_helpers::get_hue_f32,
+            (SortingCriteria::Hue, true) => |p| color_helpers::get_hue(p) as f32,
         }
     }
 
@@ -65,27 +163,54 @@ impl SpanSorter {
         };
     }
 
+    /// Returns the function that calculates the key of a pixel, for the set criteria and algorithm
+    pub fn key_function(&self) -> for<'a> fn(&'a Rgb<u8>) -> f32 {
+        // Use a special, flawed brightness function for glitchsorting
+        match (self.algorithm, self.criteria) {
+            (SortingAlgorithm::Glitchsort, SortingCriteria::Brightness) => |p| color_helpers::get_brightness_flawed(p) as f32,
+            _ => SpanSorter::get_value_function(self.criteria, self.quantize),
+        }
+    }
+
+    /// Returns the guide image stretched to the given size, if the keys are read from one
+    pub fn guide_image(&self, w: u64, h: u64) -> Option<RgbImage> {
+        match &self.key_source {
+            KeySource::Pixels => None,
+            KeySource::Image(img) if img.dimensions() == (w as u32, h as u32) => Some(RgbImage::clone(img)),
+            KeySource::Image(img) => Some(imageops::resize(img.as_ref(), w as u32, h as u32, imageops::FilterType::Triangle)),
+        }
+    }
+
     /// Sort a slice of pixels using set criteria and algorithm
     pub fn sort(&self, pixels: &mut [&mut Rgb<u8>]) {
-        // Select function per algorithm
-        let sorting_function = match self.algorithm {
-            SortingAlgorithm::DebugColor => random_color::set_random_color,
-            SortingAlgorithm::Mapsort => mapsort::mapsort_mut,
-            SortingAlgorithm::Shellsort => shellsort::shellsort_mut,
-            SortingAlgorithm::Glitchsort => glitchsort::glitchsort_mut,
-        };
-        // Use a special, flawed brightness function for glitchsorting
-        let criteria_function = match (self.algorithm, self.criteria) {
-            (SortingAlgorithm::Glitchsort, SortingCriteria::Brightness) => color_helpers::get_brightness_flawed,
-            _ => SpanSorter::get_value_function(self.criteria),
-        };
+        let key_function = self.key_function();
+        let keys: Vec<f32> = pixels.iter().map(|px| key_function(px)).collect();
+        self.sort_by_keys(pixels, &keys, SpanInfo::default());
+    }
+
+    /// Sort a slice of pixels by the given keys (one for every pixel) using the set algorithm.
+    /// `span` tells the debug colors where the pixels are and seeds the random orders
+    pub fn sort_by_keys(&self, pixels: &mut [&mut Rgb<u8>], keys: &[f32], span: SpanInfo) {
+        // Skip sorting a span if it contains less than 2 pixels, but apply debug color even on every span
+        if pixels.len() < 2 && !matches!(self.algorithm, SortingAlgorithm::DebugColor(_)) {
+            return;
+        }
         match self.algorithm {
-            // Apply debug color even on every span
-            SortingAlgorithm::DebugColor => sorting_function(pixels, criteria_function),
-            // Skip sorting a span if it contains less than 2 pixels
-            _ => if pixels.len() < 2 {return;},
+            SortingAlgorithm::DebugColor(coloring) => random_color::set_debug_color(pixels, coloring, span),
+            SortingAlgorithm::Mapsort => mapsort::mapsort_mut(pixels, keys, !self.quantize),
+            SortingAlgorithm::Shellsort => shellsort::shellsort_mut(pixels, keys),
+            SortingAlgorithm::Glitchsort => {
+                // The glitches need the integer keys
+                let keys: Vec<u16> = keys.iter().map(|&k| k as u16).collect();
+                glitchsort::glitchsort_mut(pixels, &keys)
+            }
+            SortingAlgorithm::Stablesort(ties) => stablesort::stablesort_mut(pixels, keys, ties, span),
+            SortingAlgorithm::Reverse => effects::reverse(pixels),
+            SortingAlgorithm::Shuffle => effects::shuffle(pixels, span.seed(keys)),
+            SortingAlgorithm::Smear => effects::smear(pixels),
+            SortingAlgorithm::Average => effects::average(pixels),
+            SortingAlgorithm::Gradient => effects::gradient(pixels),
+            SortingAlgorithm::Rotate(shift) => effects::rotate(pixels, shift),
         }
-        // call sorting function
-        sorting_function(pixels, criteria_function);
     }
 }
@@ -0,0 +1,109 @@
+use image::Rgb;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+// Span effects that don't sort by the keys, they just move or mix the colors of a span
+
+/// Returns a copy of the colors of the span
+fn colors(pixels: &[&mut Rgb<u8>]) -> Vec<Rgb<u8>> {
+    pixels.iter().map(|px| **px).collect()
+}
+
+/// Writes the colors back at the pointer locations
+fn write_back(pixels: &mut [&mut Rgb<u8>], colors: impl IntoIterator<Item = Rgb<u8>>) {
+    for (px, color) in pixels.iter_mut().zip(colors) {
+        **px = color;
+    }
+}
+
+pub fn reverse(pixels: &mut [&mut Rgb<u8>]) {
+    let colors = colors(pixels);
+    write_back(pixels, colors.into_iter().rev());
+}
+
+/// Puts the span in a random order. The same seed gives the same order
+pub fn shuffle(pixels: &mut [&mut Rgb<u8>], seed: u64) {
+    let mut colors = colors(pixels);
+    colors.shuffle(&mut StdRng::seed_from_u64(seed));
+    write_back(pixels, colors);
+}
+
+/// Fills the span with its first pixel
+pub fn smear(pixels: &mut [&mut Rgb<u8>]) {
+    let first = *pixels[0];
+    write_back(pixels, std::iter::repeat(first));
+}
+
+/// Fills the span with its average color
+pub fn average(pixels: &mut [&mut Rgb<u8>]) {
+    let mut sum = [0u64; 3];
+    for px in pixels.iter() {
+        for (s, &ch) in sum.iter_mut().zip(&px.0) {
+            *s += ch as u64;
+        }
+    }
+    let n = pixels.len() as u64;
+    let avg = Rgb(sum.map(|s| ((s + n / 2) / n) as u8));
+    write_back(pixels, std::iter::repeat(avg));
+}
+
+/// Fills the span with a linear gradient from its first to its last pixel
+pub fn gradient(pixels: &mut [&mut Rgb<u8>]) {
+    let (first, last) = (*pixels[0], *pixels[pixels.len() - 1]);
+    let steps = (pixels.len() - 1) as f32;
+    let colors = (0..pixels.len()).map(|i| {
+        let t = i as f32 / steps;
+        Rgb(std::array::from_fn(|ch| (first.0[ch] as f32 * (1.0 - t) + last.0[ch] as f32 * t).round() as u8))
+    });
+    write_back(pixels, colors);
+}
+
+/// Shifts the span cyclically by `shift` pixels along the path. Negative values shift backwards
+pub fn rotate(pixels: &mut [&mut Rgb<u8>], shift: i32) {
+    let mut colors = colors(pixels);
+    let shift = (shift as i64).rem_euclid(colors.len() as i64) as usize;
+    colors.rotate_right(shift);
+    write_back(pixels, colors);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs an effect on pixels whose red channel is their position
+    fn apply(len: u8, effect: impl Fn(&mut [&mut Rgb<u8>])) -> Vec<u8> {
+        let mut colors: Vec<Rgb<u8>> = (0..len).map(|i| Rgb([i, 0, 0])).collect();
+        let mut pixels: Vec<&mut Rgb<u8>> = colors.iter_mut().collect();
+        effect(&mut pixels);
+        colors.iter().map(|c| c.0[0]).collect()
+    }
+
+    #[test]
+    fn rotate_shifts_both_ways() {
+        assert_eq!(apply(5, |p| rotate(p, 2)), vec![3, 4, 0, 1, 2]);
+        assert_eq!(apply(5, |p| rotate(p, -2)), vec![2, 3, 4, 0, 1]);
+        // Shifts longer than the span wrap around
+        assert_eq!(apply(5, |p| rotate(p, -7)), apply(5, |p| rotate(p, -2)));
+        assert_eq!(apply(5, |p| rotate(p, 12)), apply(5, |p| rotate(p, 2)));
+        // -2147483648 is 2 more than a multiple of 5
+        assert_eq!(apply(5, |p| rotate(p, i32::MIN)), vec![3, 4, 0, 1, 2]);
+        assert_eq!(apply(1, |p| rotate(p, -3)), vec![0]);
+    }
+
+    #[test]
+    fn shuffle_is_seeded() {
+        let shuffled = apply(32, |p| shuffle(p, 1));
+        assert_eq!(shuffled, apply(32, |p| shuffle(p, 1)));
+        assert_ne!(shuffled, apply(32, |p| shuffle(p, 2)));
+        let mut sorted = shuffled.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..32).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn fills_use_the_span_ends() {
+        assert_eq!(apply(4, reverse), vec![3, 2, 1, 0]);
+        assert_eq!(apply(4, smear), vec![0, 0, 0, 0]);
+        assert_eq!(apply(4, average), vec![2, 2, 2, 2]);
+        assert_eq!(apply(7, gradient), vec![0, 1, 2, 3, 4, 5, 6]);
+    }
+}
@@ -32,13 +32,12 @@ fn glitch_swap(
     // But they still point to their initial index
 }
 
-pub fn glitchsort_mut(pixels: &mut [&mut Rgb<u8>], value_function: for<'a> fn(&'a Rgb<u8>) -> u16) {
+pub fn glitchsort_mut(pixels: &mut [&mut Rgb<u8>], keys: &[u16]) {
     let span_len = pixels.len() - 1;
     let mut wrappers = Vec::new();
 
     for i in 0..pixels.len() {
-        let val = value_function(pixels[i]);
-        wrappers.push(PixelWrapper { ind: i, val });
+        wrappers.push(PixelWrapper { ind: i, val: keys[i] });
     }
 
     let mut gap = span_len;
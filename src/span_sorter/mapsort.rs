@@ -1,22 +1,26 @@
 use ::array_init::array_init;
 use image::{Rgb, RgbImage};
 
-/// Sorts a slice of Pixels by either Hue, Brightness or Saturation.
-/// It uses an array with 360 elements to map the values.
-/// Probably panics if a compare function returns a value above 360
-pub fn mapsort_mut(pixels: &mut [&mut Rgb<u8>], get_pixel_value: for<'a> fn(&'a Rgb<u8>) -> u16) {
+/// Sorts a slice of Pixels by their keys (f.e. Hue, Brightness or Saturation).
+/// It uses an array with 360 elements to map the integer part of the values.
+/// With `sort_buckets`, pixels in the same element are sorted by the whole value afterwards, which isn't O(n) anymore.
+/// Probably panics if a key is above 360
+pub fn mapsort_mut(pixels: &mut [&mut Rgb<u8>], keys: &[f32], sort_buckets: bool) {
 
-    let mut map_array: [Vec<Rgb<u8>>; 360] = array_init(|_| Vec::new());
+    let mut map_array: [Vec<(f32, Rgb<u8>)>; 360] = array_init(|_| Vec::new());
 
     // we copy the pixels into the map array
     for p in 0..pixels.len() {
-        map_array[get_pixel_value(&pixels[p]) as usize].push(pixels[p].clone());
+        map_array[keys[p] as usize].push((keys[p], pixels[p].clone()));
     }
 
     // and then put them back at the pointer locations
     let mut ind = 0;
-    for h in map_array {
-        for p in h {
+    for mut h in map_array {
+        if sort_buckets {
+            h.sort_by(|a, b| a.0.total_cmp(&b.0));
+        }
+        for (_, p) in h {
             *(pixels[ind]) = p;
             ind += 1;
         }
@@ -1,18 +1,42 @@
 use image::Rgb;
 use rand::{rngs::*, Rng, SeedableRng};
 
-pub fn set_random_color(pixels: &mut [&mut Rgb<u8>], _: for<'a> fn(&'a Rgb<u8>) -> u16 ) {
+use super::{DebugColoring, SpanInfo};
+
+/// Paints the whole span in one color. The color only depends on the span, so it stays the same between sorts
+pub fn set_debug_color(pixels: &mut [&mut Rgb<u8>], coloring: DebugColoring, span: SpanInfo) {
+    let color = match coloring {
+        DebugColoring::Start => seeded_color(span.start),
+        DebugColoring::Index => seeded_color(span.index as u64),
+        DebugColoring::Length => length_color(pixels.len()),
+    };
     // put them back at the pointer locations
-    let mut rng = rand::thread_rng();
-    //let mut rng = StdRng::seed_from_u64(pixels.get(0).and_then(|p| Some(p.0[0] as u64)).unwrap_or(123198412));
-    let ran_col = Rgb {
+    for p in pixels {
+        **(p) = color;
+    }
+}
+
+/// A random color, the same for the same seed
+fn seeded_color(seed: u64) -> Rgb<u8> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    Rgb {
         0: [
             rng.gen_range(80..=240),
             rng.gen_range(80..=240),
             rng.gen_range(80..=240),
         ],
-    };
-    for p in pixels {
-        **(p) = ran_col;
     }
 }
+
+/// Goes from blue for single pixels to red for spans of 4096 pixels and longer
+fn length_color(len: usize) -> Rgb<u8> {
+    let t = ((len.max(1) as f32).log2() / 12.0).min(1.0);
+    let hue = 240.0 * (1.0 - t);
+    // HSV to RGB, with the same brightness range as the random colors
+    let channel = |n: f32| {
+        let k = (n + hue / 60.0) % 6.0;
+        let v = 1.0 - (k.min(4.0 - k).clamp(0.0, 1.0)) * 0.65;
+        (80.0 + v * 160.0).round() as u8
+    };
+    Rgb([channel(5.0), channel(3.0), channel(1.0)])
+}
@@ -3,17 +3,16 @@ use image::Rgb;
 #[derive(Debug)]
 struct PixelWrapper{
     px:  Rgb<u8>,
-    val: u16,
+    val: f32,
 }
 
-pub fn shellsort_mut(pixels: &mut [&mut Rgb<u8>], value_function: for<'a> fn(&'a Rgb<u8>) -> u16){
+pub fn shellsort_mut(pixels: &mut [&mut Rgb<u8>], keys: &[f32]){
     // Stolen from some Stackoverflow Thread
 
     let span_len = pixels.len();
     let mut fake_pixels = Vec::new();
-    // Wrap each pixel into a wrapper with a calculated value (TODO: from SortingCriteria)
-    pixels.into_iter().for_each(|px| {
-        let val = value_function(px);
+    // Wrap each pixel into a wrapper with its key
+    pixels.iter().zip(keys).for_each(|(px, &val)| {
         fake_pixels.push(PixelWrapper{px: **px, val});
     });
 
@@ -0,0 +1,56 @@
+use image::Rgb;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use super::{SpanInfo, TiePolicy};
+
+/// Stable merge sort (the one of std) on float keys, so pixels with equal keys keep an order given by `ties`.
+/// Random ties are seeded by the span and its keys (see [SpanInfo::seed]), so a span always comes out the same
+pub fn stablesort_mut(pixels: &mut [&mut Rgb<u8>], keys: &[f32], ties: TiePolicy, span: SpanInfo) {
+    // Put the pixels in the order equal keys should end up in, the stable sort keeps it
+    let mut order: Vec<usize> = (0..pixels.len()).collect();
+    match ties {
+        TiePolicy::Keep => {}
+        TiePolicy::Reverse => order.reverse(),
+        TiePolicy::Random => order.shuffle(&mut StdRng::seed_from_u64(span.seed(keys))),
+    }
+    order.sort_by(|&a, &b| keys[a].total_cmp(&keys[b]));
+
+    // put them back at the pointer locations
+    let sorted: Vec<Rgb<u8>> = order.iter().map(|&i| *pixels[i]).collect();
+    for (px, color) in pixels.iter_mut().zip(sorted) {
+        **px = color;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sorts pixels whose red channel is the key, the blue channel tells the original position
+    fn sorted(keys: &[f32], ties: TiePolicy, span: SpanInfo) -> Vec<u8> {
+        let mut colors: Vec<Rgb<u8>> = keys.iter().enumerate().map(|(i, &k)| Rgb([k as u8, 0, i as u8])).collect();
+        let mut pixels: Vec<&mut Rgb<u8>> = colors.iter_mut().collect();
+        stablesort_mut(&mut pixels, keys, ties, span);
+        colors.iter().map(|c| c.0[2]).collect()
+    }
+
+    #[test]
+    fn keep_and_reverse_ties() {
+        let keys = [2.0, 1.0, 2.0, 0.5, 1.0, 2.0];
+        assert_eq!(sorted(&keys, TiePolicy::Keep, SpanInfo::default()), vec![3, 1, 4, 0, 2, 5]);
+        assert_eq!(sorted(&keys, TiePolicy::Reverse, SpanInfo::default()), vec![3, 4, 1, 5, 2, 0]);
+    }
+
+    #[test]
+    fn random_ties_are_deterministic_per_span() {
+        let keys = [1.0; 32];
+        let a = sorted(&keys, TiePolicy::Random, SpanInfo { start: 5, index: 0 });
+        assert_eq!(a, sorted(&keys, TiePolicy::Random, SpanInfo { start: 5, index: 0 }));
+        assert_ne!(a, sorted(&keys, TiePolicy::Random, SpanInfo { start: 6, index: 0 }));
+        // Without a position, different keys still give a different order
+        let b = sorted(&keys, TiePolicy::Random, SpanInfo::default());
+        let mut other_keys = keys;
+        other_keys[31] = 2.0;
+        assert_ne!(b[..31], sorted(&other_keys, TiePolicy::Random, SpanInfo::default())[..31]);
+    }
+}